@@ -2,14 +2,41 @@ use crate::*;
 
 use crate::keyboard::KeyState;
 use libretro_rs::c_utf8::c_utf8;
-use libretro_rs::retro::env::{Init, UnloadGame};
+use libretro_rs::retro::env::{Init, SetEnvironment, UnloadGame};
 use libretro_rs::retro::pixel::{Format, XRGB8888};
 use libretro_rs::retro::*;
 use libretro_rs::{ext, libretro_core};
 use std::error::Error;
 
+#[derive(CoreOptions)]
+#[options(prefix = "chip8_")]
+pub struct Options {
+  #[option(
+    desc = "CPU Speed",
+    info = "Instructions executed per frame.",
+    min = 5,
+    max = 50,
+    step = 5,
+    default = 25
+  )]
+  speed: i32,
+  #[option(desc = "Foreground Color", default = "white")]
+  foreground: Color,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, CoreOptionValue)]
+pub enum Color {
+  #[option(label = "White")]
+  White,
+  #[option(label = "Green")]
+  Green,
+  #[option(label = "Amber")]
+  Amber,
+}
+
 pub struct LibretroCore {
   cpu: cpu::Cpu,
+  options: Options,
   audio_buffer: [i16; timer::AUDIO_BUFFER_SIZE * 2],
   frame_buffer: [XRGB8888; display::WIDTH * display::HEIGHT],
   rendering_mode: SoftwareRenderEnabled,
@@ -32,7 +59,10 @@ impl LibretroCore {
 
     for y in 0..display::HEIGHT {
       for x in 0..display::WIDTH {
-        let color = self.cpu.display.pixel(x, y).into();
+        let color = match self.cpu.display.pixel(x, y) {
+          display::Pixel::Off => XRGB8888::DEFAULT,
+          display::Pixel::On => self.options.foreground.into(),
+        };
         let index = (y * PITCH) + (x * PIXEL_SIZE);
 
         self.set_rgb(index, color);
@@ -51,12 +81,11 @@ impl LibretroCore {
 
   pub fn update_input(&mut self, runtime: &mut impl Callbacks) -> InputsPolled {
     let inputs_polled = runtime.poll_inputs();
+    let buttons = runtime.joypad_buttons(DevicePort::new(0));
     for key in keyboard::Keyboard::keys() {
-      // todo: chip-8 has a very clunky mapping to a controller.
-
-      let port = DevicePort::new(0);
-      let btn = key_to_retro_button(key);
-      if runtime.is_joypad_button_pressed(port, btn) {
+      if buttons.is_pressed(key_to_retro_button(key))
+        || runtime.is_key_pressed(key_to_retro_key(key))
+      {
         self.cpu.keyboard.set_key_state(key, KeyState::Pressed)
       } else {
         self.cpu.keyboard.set_key_state(key, KeyState::Released)
@@ -66,10 +95,68 @@ impl LibretroCore {
   }
 }
 
+/// The joypad button for each key of the hex keypad. Most games use 2, 4, 6 and 8 as
+/// directions and 5 as the action button, so those get the D-pad and A.
+const KEY_BUTTONS: [JoypadButton; 16] = [
+  JoypadButton::X,
+  JoypadButton::L1,
+  JoypadButton::Up,
+  JoypadButton::R1,
+  JoypadButton::Left,
+  JoypadButton::A,
+  JoypadButton::Right,
+  JoypadButton::Y,
+  JoypadButton::Down,
+  JoypadButton::B,
+  JoypadButton::Select,
+  JoypadButton::Start,
+  JoypadButton::L2,
+  JoypadButton::R2,
+  JoypadButton::L3,
+  JoypadButton::R3,
+];
+
 fn key_to_retro_button(key: keyboard::Key) -> JoypadButton {
-  match key.ordinal() {
-    _ => JoypadButton::Up,
-  }
+  KEY_BUTTONS[key.ordinal() as usize]
+}
+
+/// The keyboard key for each key of the hex keypad, using the customary layout
+/// that maps the keypad onto the left side of a QWERTY keyboard:
+///
+/// ```text
+/// 1 2 3 C      1 2 3 4
+/// 4 5 6 D  ->  Q W E R
+/// 7 8 9 E      A S D F
+/// A 0 B F      Z X C V
+/// ```
+const KEY_KEYS: [RetroKey; 16] = [
+  RetroKey::X,
+  RetroKey::Num1,
+  RetroKey::Num2,
+  RetroKey::Num3,
+  RetroKey::Q,
+  RetroKey::W,
+  RetroKey::E,
+  RetroKey::A,
+  RetroKey::S,
+  RetroKey::D,
+  RetroKey::Z,
+  RetroKey::C,
+  RetroKey::Num4,
+  RetroKey::R,
+  RetroKey::F,
+  RetroKey::V,
+];
+
+fn key_to_retro_key(key: keyboard::Key) -> RetroKey {
+  KEY_KEYS[key.ordinal() as usize]
+}
+
+fn input_descriptors() -> InputDescriptors {
+  keyboard::Keyboard::keys().fold(InputDescriptors::new(), |descriptors, key| {
+    let description = format!("Key {:X}", key.ordinal());
+    descriptors.with_joypad(DevicePort::new(0), key_to_retro_button(key), description)
+  })
 }
 
 impl<'a> Core<'a> for LibretroCore {
@@ -83,6 +170,10 @@ impl<'a> Core<'a> for LibretroCore {
     )
   }
 
+  fn set_environment(env: &mut impl SetEnvironment) {
+    let _ = env.set_core_options::<Options>();
+  }
+
   fn init(_env: &mut impl Init) -> Self::Init {
     ()
   }
@@ -98,11 +189,13 @@ impl<'a> Core<'a> for LibretroCore {
       ..
     } = args;
     let pixel_format = env.set_pixel_format_xrgb8888(pixel_format)?;
+    let _ = env.set_input_descriptors(input_descriptors());
     let data: &[u8] = game.as_data().ok_or(CoreError::new())?.data();
     Ok(Self {
       rendering_mode,
       pixel_format,
       cpu: cpu::Cpu::new(data),
+      options: env.get_core_options(),
       audio_buffer: [0; timer::AUDIO_BUFFER_SIZE * 2],
       frame_buffer: [XRGB8888::DEFAULT; display::AREA],
     })
@@ -115,10 +208,11 @@ impl<'a> Core<'a> for LibretroCore {
     SystemAVInfo::default_timings(GameGeometry::fixed(WINDOW_WIDTH, WINDOW_HEIGHT))
   }
 
-  fn run(&mut self, _env: &mut impl env::Run, callbacks: &mut impl Callbacks) -> InputsPolled {
+  fn run(&mut self, env: &mut impl env::Run, callbacks: &mut impl Callbacks) -> InputsPolled {
+    env.get_core_options_update(&mut self.options);
     let inputs_polled = self.update_input(callbacks);
 
-    self.cpu.step_for(25);
+    self.cpu.step_for(self.options.speed);
 
     self.render_audio(callbacks);
     self.render_video(callbacks);
@@ -136,11 +230,12 @@ impl<'a> Core<'a> for LibretroCore {
 
 libretro_core!(crate::libretro::LibretroCore);
 
-impl From<display::Pixel> for XRGB8888 {
-  fn from(pixel: display::Pixel) -> Self {
-    match pixel {
-      display::Pixel::Off => XRGB8888::DEFAULT,
-      display::Pixel::On => XRGB8888::new_with_raw_value(0x00FFFFFF),
+impl From<Color> for XRGB8888 {
+  fn from(color: Color) -> Self {
+    match color {
+      Color::White => XRGB8888::new_with_raw_value(0x00FFFFFF),
+      Color::Green => XRGB8888::new_with_raw_value(0x0033FF33),
+      Color::Amber => XRGB8888::new_with_raw_value(0x00FFB000),
     }
   }
 }
@@ -0,0 +1,97 @@
+//! Derive macros for `libretro-rs`.
+//!
+//! These macros are re-exported by `libretro-rs` when its `derive` feature is
+//! enabled. See `libretro_rs::retro::options` for how to use them.
+
+mod options;
+mod value;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned};
+use syn::parse::ParseStream;
+use syn::{parse_macro_input, DeriveInput, Error, LitByteStr, LitStr, Result, Token};
+
+/// Derives `CoreOptions` for a struct with named fields.
+#[proc_macro_derive(CoreOptions, attributes(options, option))]
+pub fn derive_core_options(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  options::derive(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+/// Derives `CoreOptionValue` for an enum with unit variants.
+#[proc_macro_derive(CoreOptionValue, attributes(option))]
+pub fn derive_core_option_value(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  value::derive(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+/// Path to the options module of `libretro-rs`.
+fn options_path() -> TokenStream2 {
+  quote!(::libretro_rs::retro::options)
+}
+
+/// Converts a string literal into a `&'static CStr` expression.
+fn c_str(lit: &LitStr) -> Result<TokenStream2> {
+  c_str_from(&lit.value(), lit)
+}
+
+/// Converts a string into a `&'static CStr` expression, reporting errors at `span`.
+fn c_str_from(value: &str, span: &impl quote::ToTokens) -> Result<TokenStream2> {
+  if value.contains('\0') {
+    return Err(Error::new_spanned(
+      span,
+      "strings must not contain nul bytes",
+    ));
+  }
+  let mut bytes = value.as_bytes().to_vec();
+  bytes.push(0);
+  let bytes = LitByteStr::new(&bytes, proc_macro2::Span::call_site());
+  let path = options_path();
+  Ok(quote!(#path::__c_str(#bytes)))
+}
+
+/// Parses an integer literal with an optional leading minus sign.
+fn parse_int(input: ParseStream) -> Result<i64> {
+  let negative = input.peek(Token![-]);
+  if negative {
+    input.parse::<Token![-]>()?;
+  }
+  let lit: syn::LitInt = input.parse()?;
+  let value: i64 = lit.base10_parse()?;
+  Ok(if negative { -value } else { value })
+}
+
+/// Converts `snake_case` into `CamelCase`.
+fn camel_case(ident: &str) -> String {
+  ident
+    .split('_')
+    .map(|word| {
+      let mut chars = word.chars();
+      chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+        .unwrap_or_default()
+    })
+    .collect()
+}
+
+/// Converts `CamelCase` into `snake_case`.
+fn snake_case(ident: &str) -> String {
+  let mut out = String::with_capacity(ident.len() + 4);
+  for (i, c) in ident.char_indices() {
+    if c.is_uppercase() {
+      if i > 0 {
+        out.push('_');
+      }
+      out.extend(c.to_lowercase());
+    } else {
+      out.push(c);
+    }
+  }
+  out
+}
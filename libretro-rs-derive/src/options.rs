@@ -0,0 +1,520 @@
+use crate::*;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, Field, Fields, Ident, Lit, LitStr};
+
+/// The maximum number of values of a single option, excluding the terminator.
+const MAX_VALUES: usize = 127;
+
+struct Category {
+  key: LitStr,
+  desc: LitStr,
+  info: Option<LitStr>,
+}
+
+#[derive(Default)]
+struct StructAttrs {
+  prefix: Option<LitStr>,
+  categories: Vec<Category>,
+  translations: Option<syn::Expr>,
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+  key: Option<LitStr>,
+  desc: Option<LitStr>,
+  desc_categorized: Option<LitStr>,
+  info: Option<LitStr>,
+  info_categorized: Option<LitStr>,
+  category: Option<LitStr>,
+  default: Option<LitStr>,
+  min: Option<i64>,
+  max: Option<i64>,
+  step: Option<i64>,
+  visible_if: Vec<(Ident, Vec<LitStr>)>,
+}
+
+struct OptionField {
+  ident: Ident,
+  ty: syn::Type,
+  key: LitStr,
+  attrs: FieldAttrs,
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => &fields.named,
+      _ => {
+        return Err(Error::new_spanned(
+          &input.ident,
+          "CoreOptions can only be derived for structs with named fields",
+        ))
+      }
+    },
+    _ => {
+      return Err(Error::new_spanned(
+        &input.ident,
+        "CoreOptions can only be derived for structs",
+      ))
+    }
+  };
+  if !input.generics.params.is_empty() {
+    return Err(Error::new_spanned(
+      &input.generics,
+      "CoreOptions cannot be derived for generic structs",
+    ));
+  }
+
+  let struct_attrs = parse_struct_attrs(&input)?;
+  let fields = fields
+    .iter()
+    .map(|field| parse_field(field, &struct_attrs))
+    .collect::<Result<Vec<_>>>()?;
+
+  let path = options_path();
+  let name = &input.ident;
+  let vis = &input.vis;
+  let change = format_ident!("{}Change", name);
+
+  let categories = struct_attrs
+    .categories
+    .iter()
+    .map(|category| {
+      let key = c_str(&category.key)?;
+      let desc = c_str(&category.desc)?;
+      let info = optional(".with_info", &category.info)?;
+      Ok(quote!(#path::OptionCategory::new(#key, #desc) #info))
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+  let mut definitions = Vec::new();
+  let mut assertions = Vec::new();
+  let mut initializers = Vec::new();
+  let mut variants = Vec::new();
+  let mut updates = Vec::new();
+  let mut definition_arms = Vec::new();
+  let mut value_arms = Vec::new();
+  for (index, field) in fields.iter().enumerate() {
+    let OptionField {
+      ident,
+      ty,
+      key,
+      attrs,
+    } = field;
+    let desc = attrs
+      .desc
+      .as_ref()
+      .ok_or_else(|| Error::new_spanned(ident, "missing `desc` for core option"))?;
+
+    if let Some(category) = &attrs.category {
+      if !struct_attrs
+        .categories
+        .iter()
+        .any(|c| c.key.value() == category.value())
+      {
+        return Err(Error::new_spanned(category, "unknown core option category"));
+      }
+    }
+
+    let values = match range_values(field)? {
+      Some(values) => {
+        let (min, max) = (int_lit(attrs.min, ty.span()), int_lit(attrs.max, ty.span()));
+        assertions.push(quote_spanned! {ty.span()=>
+          let _: #ty = #min;
+          let _: #ty = #max;
+        });
+        let values = values
+          .iter()
+          .map(|value| {
+            let value = c_str_from(value, key)?;
+            Ok(quote!(#path::OptionValue::new(#value)))
+          })
+          .collect::<Result<Vec<_>>>()?;
+        quote!(&[#(#values),*])
+      }
+      None => {
+        assertions.push(quote! {
+          ::core::assert!(
+            !<#ty as #path::CoreOptionValue>::VALUES.is_empty(),
+            ::core::concat!("core option `", #key, "` must declare its values with `min` and `max`")
+          );
+        });
+        if let Some(default) = &attrs.default {
+          let default = c_str(default)?;
+          assertions.push(quote! {
+            ::core::assert!(
+              #path::__has_value(<#ty as #path::CoreOptionValue>::VALUES, #default),
+              ::core::concat!("default value of core option `", #key, "` is not one of its values")
+            );
+          });
+        }
+        quote!(<#ty as #path::CoreOptionValue>::VALUES)
+      }
+    };
+
+    let key_c = c_str(key)?;
+    let desc = c_str(desc)?;
+    let desc_categorized = optional(".with_desc_categorized", &attrs.desc_categorized)?;
+    let info = optional(".with_info", &attrs.info)?;
+    let info_categorized = optional(".with_info_categorized", &attrs.info_categorized)?;
+    let category = optional(".with_category", &attrs.category)?;
+    let default = optional(".with_default", &attrs.default)?;
+    let visible_if = visible_if(field, &fields, &mut assertions)?;
+    definitions.push(quote! {
+      #path::OptionDefinition::new(#key_c, #desc, #values)
+        #desc_categorized #info #info_categorized #category #default #visible_if
+    });
+    initializers.push(quote! {
+      #ident: DEFINITIONS[#index].parse(get(DEFINITIONS[#index].key()))
+    });
+
+    let variant = format_ident!("{}", camel_case(&ident.to_string()), span = ident.span());
+    variants.push(quote!(#variant(#ty)));
+    definition_arms.push(quote!(#change::#variant(_) => &DEFINITIONS[#index]));
+    value_arms.push(quote! {
+      #change::#variant(ref value) => #path::CoreOptionValue::to_value(value)
+    });
+    updates.push(quote! {
+      if self.#ident != new.#ident {
+        changes.push(#change::#variant(::core::clone::Clone::clone(&new.#ident)));
+        self.#ident = new.#ident;
+      }
+    });
+  }
+
+  let translations = struct_attrs.translations.as_ref().map(|translations| {
+    quote!(const TRANSLATIONS: &'static [#path::OptionsTranslation] = #translations;)
+  });
+
+  let body = if fields.is_empty() {
+    quote!(let _ = &mut get; Self {})
+  } else {
+    quote!(Self { #(#initializers,)* })
+  };
+
+  Ok(quote! {
+    const _: () = {
+      #(#assertions)*
+    };
+
+    #[doc = ::core::concat!("A change to one of the options in [`", ::core::stringify!(#name), "`].")]
+    #[derive(Clone, Debug, PartialEq)]
+    #vis enum #change {
+      #(#variants,)*
+    }
+
+    impl #path::OptionChange for #change {
+      fn definition(&self) -> &'static #path::OptionDefinition {
+        const DEFINITIONS: &'static [#path::OptionDefinition] =
+          <#name as #path::CoreOptions>::DEFINITIONS;
+        match *self {
+          #(#definition_arms,)*
+        }
+      }
+
+      fn value(&self) -> ::std::borrow::Cow<'static, ::core::ffi::CStr> {
+        match *self {
+          #(#value_arms,)*
+        }
+      }
+    }
+
+    impl #path::CoreOptions for #name {
+      type Change = #change;
+
+      const CATEGORIES: &'static [#path::OptionCategory] = &[#(#categories),*];
+
+      const DEFINITIONS: &'static [#path::OptionDefinition] = &[#(#definitions),*];
+
+      #translations
+
+      fn from_values<'a>(
+        mut get: impl FnMut(&'static ::core::ffi::CStr) -> ::core::option::Option<&'a ::core::ffi::CStr>,
+      ) -> Self {
+        const DEFINITIONS: &'static [#path::OptionDefinition] =
+          <#name as #path::CoreOptions>::DEFINITIONS;
+        #body
+      }
+
+      fn update(&mut self, new: Self) -> ::std::vec::Vec<Self::Change> {
+        let mut changes = ::std::vec::Vec::new();
+        #(#updates)*
+        changes
+      }
+    }
+  })
+}
+
+fn parse_struct_attrs(input: &DeriveInput) -> Result<StructAttrs> {
+  let mut attrs = StructAttrs::default();
+  for attr in input.attrs.iter().filter(|a| a.path().is_ident("options")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("prefix") {
+        attrs.prefix = Some(meta.value()?.parse()?);
+      } else if meta.path.is_ident("category") {
+        attrs.categories.push(parse_category(&meta)?);
+      } else if meta.path.is_ident("translations") {
+        attrs.translations = Some(meta.value()?.parse()?);
+      } else {
+        return Err(meta.error("expected `prefix`, `category` or `translations`"));
+      }
+      Ok(())
+    })?;
+  }
+  Ok(attrs)
+}
+
+fn parse_category(meta: &ParseNestedMeta) -> Result<Category> {
+  let mut key = None;
+  let mut desc = None;
+  let mut info = None;
+  meta.parse_nested_meta(|meta| {
+    if meta.path.is_ident("key") {
+      key = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("desc") {
+      desc = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("info") {
+      info = Some(meta.value()?.parse()?);
+    } else {
+      return Err(meta.error("expected `key`, `desc` or `info`"));
+    }
+    Ok(())
+  })?;
+  Ok(Category {
+    key: key.ok_or_else(|| meta.error("missing `key` for core option category"))?,
+    desc: desc.ok_or_else(|| meta.error("missing `desc` for core option category"))?,
+    info,
+  })
+}
+
+fn parse_field(field: &Field, struct_attrs: &StructAttrs) -> Result<OptionField> {
+  let ident = field.ident.clone().expect("named fields have identifiers");
+  let mut attrs = FieldAttrs::default();
+  let mut found = false;
+  for attr in field.attrs.iter().filter(|a| a.path().is_ident("option")) {
+    found = true;
+    attr.parse_nested_meta(|meta| {
+      let path = &meta.path;
+      if path.is_ident("key") {
+        attrs.key = Some(meta.value()?.parse()?);
+      } else if path.is_ident("desc") {
+        attrs.desc = Some(meta.value()?.parse()?);
+      } else if path.is_ident("desc_categorized") {
+        attrs.desc_categorized = Some(meta.value()?.parse()?);
+      } else if path.is_ident("info") {
+        attrs.info = Some(meta.value()?.parse()?);
+      } else if path.is_ident("info_categorized") {
+        attrs.info_categorized = Some(meta.value()?.parse()?);
+      } else if path.is_ident("category") {
+        attrs.category = Some(meta.value()?.parse()?);
+      } else if path.is_ident("default") {
+        attrs.default = Some(parse_default(meta.value()?)?);
+      } else if path.is_ident("min") {
+        attrs.min = Some(parse_int(meta.value()?)?);
+      } else if path.is_ident("max") {
+        attrs.max = Some(parse_int(meta.value()?)?);
+      } else if path.is_ident("step") {
+        attrs.step = Some(parse_int(meta.value()?)?);
+      } else if path.is_ident("visible_if") {
+        meta.parse_nested_meta(|meta| {
+          attrs.visible_if.push(parse_condition(&meta)?);
+          Ok(())
+        })?;
+      } else {
+        return Err(meta.error("unknown core option attribute"));
+      }
+      Ok(())
+    })?;
+  }
+  if !found {
+    return Err(Error::new_spanned(
+      &ident,
+      "every field of a CoreOptions struct needs an `#[option(...)]` attribute",
+    ));
+  }
+
+  let key = match &attrs.key {
+    Some(key) => key.clone(),
+    None => {
+      let prefix = struct_attrs
+        .prefix
+        .as_ref()
+        .map(LitStr::value)
+        .unwrap_or_default();
+      LitStr::new(&format!("{}{}", prefix, ident), ident.span())
+    }
+  };
+  Ok(OptionField {
+    ident,
+    ty: field.ty.clone(),
+    key,
+    attrs,
+  })
+}
+
+/// Parses a visibility condition of the form `field = value` or `field = [values...]`.
+fn parse_condition(meta: &ParseNestedMeta) -> Result<(Ident, Vec<LitStr>)> {
+  let field = meta
+    .path
+    .get_ident()
+    .cloned()
+    .ok_or_else(|| meta.error("expected the name of another field"))?;
+  let input = meta.value()?;
+  let values = if input.peek(syn::token::Bracket) {
+    let content;
+    syn::bracketed!(content in input);
+    Punctuated::<LitStr, Token![,]>::parse_terminated_with(&content, parse_default)?
+      .into_iter()
+      .collect()
+  } else {
+    vec![parse_default(input)?]
+  };
+  if values.is_empty() {
+    return Err(meta.error("expected at least one value"));
+  }
+  Ok((field, values))
+}
+
+/// Parses an option value. Booleans use the libretro convention of
+/// `"enabled"` and `"disabled"`; integers use their decimal representation.
+fn parse_default(input: ParseStream) -> Result<LitStr> {
+  if input.peek(Token![-]) {
+    let value = parse_int(input)?;
+    return Ok(LitStr::new(&value.to_string(), input.span()));
+  }
+  let lit: Lit = input.parse()?;
+  let value = match &lit {
+    Lit::Str(s) => s.value(),
+    Lit::Bool(b) => String::from(if b.value { "enabled" } else { "disabled" }),
+    Lit::Int(i) => i.base10_parse::<i64>()?.to_string(),
+    _ => {
+      return Err(Error::new_spanned(
+        lit,
+        "expected a string, bool or integer",
+      ))
+    }
+  };
+  Ok(LitStr::new(&value, lit.span()))
+}
+
+/// Returns the values of an integer option declared with `min`, `max` and `step`.
+fn range_values(field: &OptionField) -> Result<Option<Vec<String>>> {
+  let attrs = &field.attrs;
+  let (min, max) = match (attrs.min, attrs.max) {
+    (Some(min), Some(max)) => (min, max),
+    (None, None) if attrs.step.is_none() => return Ok(None),
+    _ => {
+      return Err(Error::new_spanned(
+        &field.ident,
+        "integer core options need both `min` and `max`",
+      ))
+    }
+  };
+  let step = attrs.step.unwrap_or(1);
+  if step <= 0 || min > max {
+    return Err(Error::new_spanned(
+      &field.ident,
+      "expected `min <= max` and a positive `step`",
+    ));
+  }
+  let count = (max - min) / step + 1;
+  if count as usize > MAX_VALUES {
+    return Err(Error::new_spanned(
+      &field.ident,
+      format!("core options can have at most {} values", MAX_VALUES),
+    ));
+  }
+  let values: Vec<String> = (0..count).map(|n| (min + n * step).to_string()).collect();
+  if let Some(default) = &attrs.default {
+    if !values.contains(&default.value()) {
+      return Err(Error::new_spanned(
+        default,
+        "default value is not one of the option's values",
+      ));
+    }
+  }
+  Ok(Some(values))
+}
+
+/// Generates an integer literal for a bound of a range option, so that the compiler
+/// checks that it fits the type of the option.
+fn int_lit(value: Option<i64>, span: proc_macro2::Span) -> TokenStream2 {
+  let value = value.expect("range options have both bounds");
+  let mut abs = proc_macro2::Literal::u64_unsuffixed(value.unsigned_abs());
+  abs.set_span(span);
+  if value < 0 {
+    quote_spanned!(span=> -#abs)
+  } else {
+    quote!(#abs)
+  }
+}
+
+/// Generates the visibility conditions of `field`, resolving the fields they refer to.
+/// Values of enum and bool options are checked at compile time through `assertions`.
+fn visible_if(
+  field: &OptionField,
+  fields: &[OptionField],
+  assertions: &mut Vec<TokenStream2>,
+) -> Result<TokenStream2> {
+  if field.attrs.visible_if.is_empty() {
+    return Ok(quote!());
+  }
+  let path = options_path();
+  let conditions = field
+    .attrs
+    .visible_if
+    .iter()
+    .map(|(ident, values)| {
+      let other = fields
+        .iter()
+        .find(|other| other.ident == *ident)
+        .ok_or_else(|| Error::new_spanned(ident, "unknown field"))?;
+      if other.ident == field.ident {
+        return Err(Error::new_spanned(
+          ident,
+          "an option's visibility cannot depend on itself",
+        ));
+      }
+      match range_values(other)? {
+        Some(range) => {
+          if let Some(value) = values.iter().find(|value| !range.contains(&value.value())) {
+            return Err(Error::new_spanned(
+              value,
+              "value is not one of the option's values",
+            ));
+          }
+        }
+        None => {
+          let ty = &other.ty;
+          for value in values {
+            let value_c = c_str(value)?;
+            assertions.push(quote! {
+              ::core::assert!(
+                #path::__has_value(<#ty as #path::CoreOptionValue>::VALUES, #value_c),
+                ::core::concat!("visibility condition value `", #value, "` is not one of the values of its option")
+              );
+            });
+          }
+        }
+      }
+      let key = c_str(&other.key)?;
+      let values = values.iter().map(c_str).collect::<Result<Vec<_>>>()?;
+      Ok(quote!(#path::OptionCondition::new(#key, &[#(#values),*])))
+    })
+    .collect::<Result<Vec<_>>>()?;
+  Ok(quote!(.with_visible_if(&[#(#conditions),*])))
+}
+
+/// Generates an optional builder method call.
+fn optional(method: &str, lit: &Option<LitStr>) -> Result<TokenStream2> {
+  match lit {
+    Some(lit) => {
+      let method: TokenStream2 = method.parse()?;
+      let value = c_str(lit)?;
+      Ok(quote!(#method(#value)))
+    }
+    None => Ok(quote!()),
+  }
+}
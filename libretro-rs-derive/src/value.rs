@@ -0,0 +1,103 @@
+use crate::*;
+use syn::{Data, Fields, LitStr};
+
+struct Variant {
+  ident: syn::Ident,
+  value: LitStr,
+  label: Option<LitStr>,
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+  let data = match &input.data {
+    Data::Enum(data) => data,
+    _ => {
+      return Err(Error::new_spanned(
+        &input.ident,
+        "CoreOptionValue can only be derived for enums",
+      ))
+    }
+  };
+  if data.variants.is_empty() {
+    return Err(Error::new_spanned(
+      &input.ident,
+      "CoreOptionValue requires at least one variant",
+    ));
+  }
+
+  let mut variants = Vec::new();
+  for variant in &data.variants {
+    if !matches!(variant.fields, Fields::Unit) {
+      return Err(Error::new_spanned(
+        variant,
+        "CoreOptionValue variants must not have fields",
+      ));
+    }
+    let mut value = None;
+    let mut label = None;
+    for attr in variant.attrs.iter().filter(|a| a.path().is_ident("option")) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("value") {
+          value = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("label") {
+          label = Some(meta.value()?.parse()?);
+        } else {
+          return Err(meta.error("expected `value` or `label`"));
+        }
+        Ok(())
+      })?;
+    }
+    let ident = variant.ident.clone();
+    let value = value.unwrap_or_else(|| LitStr::new(&snake_case(&ident.to_string()), ident.span()));
+    variants.push(Variant {
+      ident,
+      value,
+      label,
+    });
+  }
+
+  let path = options_path();
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  let mut values = Vec::new();
+  let mut from_arms = Vec::new();
+  let mut to_arms = Vec::new();
+  for Variant {
+    ident,
+    value,
+    label,
+  } in &variants
+  {
+    let c_value = c_str(value)?;
+    let with_label = match label {
+      Some(label) => {
+        let label = c_str(label)?;
+        quote!(.with_label(#label))
+      }
+      None => quote!(),
+    };
+    values.push(quote!(#path::OptionValue::new(#c_value) #with_label));
+    let bytes = LitByteStr::new(value.value().as_bytes(), value.span());
+    from_arms.push(quote!(#bytes => ::core::option::Option::Some(Self::#ident)));
+    to_arms.push(quote!(Self::#ident => #c_value));
+  }
+
+  Ok(quote! {
+    impl #impl_generics #path::CoreOptionValue for #name #ty_generics #where_clause {
+      const VALUES: &'static [#path::OptionValue] = &[#(#values),*];
+
+      fn from_value(value: &::core::ffi::CStr) -> ::core::option::Option<Self> {
+        match value.to_bytes() {
+          #(#from_arms,)*
+          _ => ::core::option::Option::None,
+        }
+      }
+
+      fn to_value(&self) -> ::std::borrow::Cow<'static, ::core::ffi::CStr> {
+        ::std::borrow::Cow::Borrowed(match self {
+          #(#to_arms,)*
+        })
+      }
+    }
+  })
+}
@@ -27,6 +27,41 @@ pub type non_null_retro_hw_get_current_framebuffer_t = unsafe extern "C" fn() ->
 pub type non_null_retro_hw_get_proc_address_t = unsafe extern "C" fn(sym: *const c_char) -> retro_proc_address_t;
 pub type non_null_retro_hw_context_reset_t = unsafe extern "C" fn();
 
+pub type non_null_retro_core_options_update_display_callback_t = unsafe extern "C" fn() -> bool;
+
+pub type non_null_retro_set_rumble_state_t = unsafe extern "C" fn(port: c_uint, effect: retro_rumble_effect, strength: u16) -> bool;
+pub type non_null_retro_set_sensor_state_t = unsafe extern "C" fn(port: c_uint, action: retro_sensor_action, rate: c_uint) -> bool;
+
+pub type non_null_retro_camera_start_t = unsafe extern "C" fn() -> bool;
+pub type non_null_retro_camera_stop_t = unsafe extern "C" fn();
+pub type non_null_retro_camera_lifetime_status_t = unsafe extern "C" fn();
+pub type non_null_retro_camera_frame_raw_framebuffer_t = unsafe extern "C" fn(buffer: *const u32, width: c_uint, height: c_uint, pitch: usize);
+pub type non_null_retro_camera_frame_opengl_texture_t = unsafe extern "C" fn(texture_id: c_uint, texture_target: c_uint, affine: *const f32);
+
+pub type non_null_retro_location_set_interval_t = unsafe extern "C" fn(interval_ms: c_uint, interval_distance: c_uint);
+pub type non_null_retro_location_start_t = unsafe extern "C" fn() -> bool;
+pub type non_null_retro_location_stop_t = unsafe extern "C" fn();
+pub type non_null_retro_location_get_position_t = unsafe extern "C" fn(lat: *mut f64, lon: *mut f64, horiz_accuracy: *mut f64, vert_accuracy: *mut f64) -> bool;
+pub type non_null_retro_location_lifetime_status_t = unsafe extern "C" fn();
+
+pub type non_null_retro_set_led_state_t = unsafe extern "C" fn(led: c_int, state: c_int);
+
+pub type non_null_retro_midi_input_enabled_t = unsafe extern "C" fn() -> bool;
+pub type non_null_retro_midi_output_enabled_t = unsafe extern "C" fn() -> bool;
+pub type non_null_retro_midi_read_t = unsafe extern "C" fn(byte: *mut u8) -> bool;
+pub type non_null_retro_midi_write_t = unsafe extern "C" fn(byte: u8, delta_time: u32) -> bool;
+pub type non_null_retro_midi_flush_t = unsafe extern "C" fn() -> bool;
+
+pub type non_null_retro_perf_get_time_usec_t = unsafe extern "C" fn() -> retro_time_t;
+pub type non_null_retro_perf_get_counter_t = unsafe extern "C" fn() -> retro_perf_tick_t;
+pub type non_null_retro_get_cpu_features_t = unsafe extern "C" fn() -> u64;
+pub type non_null_retro_perf_log_t = unsafe extern "C" fn();
+pub type non_null_retro_perf_register_t = unsafe extern "C" fn(counter: *mut retro_perf_counter);
+pub type non_null_retro_perf_start_t = unsafe extern "C" fn(counter: *mut retro_perf_counter);
+pub type non_null_retro_perf_stop_t = unsafe extern "C" fn(counter: *mut retro_perf_counter);
+
+pub type non_null_retro_keyboard_event_t = unsafe extern "C" fn(down: bool, keycode: c_uint, character: u32, key_modifiers: u16);
+
 pub const RETRO_HW_FRAME_BUFFER_VALID: *const c_void = sptr::invalid(usize::MAX);
 
 #[cfg(test)]
@@ -297,6 +297,23 @@ impl<'a, T> Frame<'a, T> {
     }
   }
 
+  /// Creates a frame whose rows start `pitch` pixels apart, as in a frame that was cropped
+  /// from a larger one. `data` only needs to extend to the end of the last row.
+  pub fn new_with_pitch(data: &'a [T], width: u32, height: u32, pitch: u32) -> Self {
+    assert!(pitch >= width, "pitch should be at least width");
+    let len = match height {
+      0 => 0,
+      height => pitch as usize * (height as usize - 1) + width as usize,
+    };
+    assert!(data.len() >= len, "data should contain every row");
+    Self {
+      data,
+      width,
+      height,
+      pitch,
+    }
+  }
+
   pub fn with_pitch(mut self, pitch: u32) -> Self {
     self.pitch = pitch;
     self
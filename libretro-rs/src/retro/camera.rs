@@ -0,0 +1,113 @@
+use crate::ffi::*;
+
+/// The kinds of frames a core can accept from a camera, and the resolution it would like.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CameraOptions {
+  raw_framebuffer: bool,
+  gl_texture: bool,
+  width: c_uint,
+  height: c_uint,
+}
+
+impl CameraOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Accept frames as XRGB8888 pixels, delivered to [`CameraCore::camera_frame`].
+  ///
+  /// [`CameraCore::camera_frame`]: crate::retro::CameraCore::camera_frame
+  pub fn with_raw_framebuffer(mut self) -> Self {
+    self.raw_framebuffer = true;
+    self
+  }
+
+  /// Accept frames as OpenGL textures, delivered to [`CameraCore::camera_texture`].
+  /// This requires hardware rendering.
+  ///
+  /// [`CameraCore::camera_texture`]: crate::retro::CameraCore::camera_texture
+  pub fn with_gl_texture(mut self) -> Self {
+    self.gl_texture = true;
+    self
+  }
+
+  /// Asks for frames of the given size. Frontends treat this as a hint.
+  pub fn with_size(mut self, width: c_uint, height: c_uint) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  pub fn raw_framebuffer(&self) -> bool {
+    self.raw_framebuffer
+  }
+
+  pub fn gl_texture(&self) -> bool {
+    self.gl_texture
+  }
+
+  pub fn width(&self) -> c_uint {
+    self.width
+  }
+
+  pub fn height(&self) -> c_uint {
+    self.height
+  }
+}
+
+impl From<CameraOptions> for retro_camera_callback {
+  fn from(options: CameraOptions) -> Self {
+    let mut caps = 0;
+    if options.gl_texture {
+      caps |= 1 << retro_camera_buffer::RETRO_CAMERA_BUFFER_OPENGL_TEXTURE as u64;
+    }
+    if options.raw_framebuffer {
+      caps |= 1 << retro_camera_buffer::RETRO_CAMERA_BUFFER_RAW_FRAMEBUFFER as u64;
+    }
+    Self {
+      caps,
+      width: options.width,
+      height: options.height,
+      ..Default::default()
+    }
+  }
+}
+
+/// A camera, provided by [`env::LoadGame::get_camera_interface`].
+///
+/// [`env::LoadGame::get_camera_interface`]: crate::retro::env::LoadGame::get_camera_interface
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+  start: non_null_retro_camera_start_t,
+  stop: non_null_retro_camera_stop_t,
+}
+
+impl Camera {
+  pub fn new(start: non_null_retro_camera_start_t, stop: non_null_retro_camera_stop_t) -> Self {
+    Self { start, stop }
+  }
+
+  /// Starts delivering frames. Must be called during `retro_run` or
+  /// [`CameraCore::camera_initialized`]. Returns true if the camera started.
+  ///
+  /// [`CameraCore::camera_initialized`]: crate::retro::CameraCore::camera_initialized
+  pub fn start(&self) -> bool {
+    unsafe { (self.start)() }
+  }
+
+  /// Stops delivering frames. Must be called during `retro_run`.
+  pub fn stop(&self) {
+    unsafe { (self.stop)() }
+  }
+}
+
+/// A camera frame delivered as an OpenGL texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraTexture {
+  pub id: c_uint,
+  /// The texture target, e.g. `GL_TEXTURE_2D`.
+  pub target: c_uint,
+  /// A column-major 3x3 matrix that maps texture coordinates so that `(0, 0)` is the
+  /// bottom-left corner and `(1, 1)` is the top-right corner.
+  pub affine: [f32; 9],
+}
@@ -13,14 +13,16 @@
 //! the user unloads it or shuts down the frontend.
 
 use crate::ffi::*;
+use crate::retro::device::{RawControllerInfo, RawInputDescriptors};
 use crate::retro::env::Environment;
-use crate::retro::pixel::{Format, ORGB1555};
+use crate::retro::pixel::{Format, ORGB1555, XRGB8888};
 use crate::retro::*;
 use core::ffi::*;
 use core::mem::MaybeUninit;
 use core::ops::*;
 use core::slice;
 use std::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 /// A basic libretro core.
 #[allow(unused_variables)]
@@ -101,6 +103,8 @@ pub trait SaveStateCore<'a>: Core<'a> {
 pub trait DeviceTypeAwareCore<'a>: Core<'a> {
   /// Called to associate a particular device with a particular port. A core is allowed to ignore this request.
   ///
+  /// `device` may be one of the [`DeviceSubclass`]es the core advertised with [`ControllerInfo`].
+  ///
   /// This function returns [`Result`] to make error handling easier.
   /// The libretro function `retro_set_controller_port_device` does not return a result to the frontend.
   fn set_controller_port_device(
@@ -128,6 +132,71 @@ pub trait CheatsCore<'a>: Core<'a> {
   fn cheat_reset(&mut self, env: &mut impl env::CheatReset);
 }
 
+/// Disk swapping for content made of several disk images, such as multi-disc games.
+///
+/// The [`libretro_core`] macro registers the disk control interface during `retro_init`.
+/// Frontends that support version 1 or later of the interface get the extended interface,
+/// which adds [`DiskControlCore::set_initial_image`], [`DiskControlCore::get_image_path`] and
+/// [`DiskControlCore::get_image_label`]; other frontends get the legacy interface.
+///
+/// To swap disks, frontends open the tray, set the image index and close the tray.
+#[allow(unused_variables)]
+pub trait DiskControlCore<'a>: Core<'a> {
+  /// Opens (`ejected` is true) or closes the virtual disk tray. Returns true if successful.
+  fn set_eject_state(&mut self, env: &mut impl env::SetEjectState, ejected: bool) -> bool;
+
+  /// Returns true if the virtual disk tray is open. The tray starts out closed.
+  fn get_eject_state(&self, env: &mut impl env::GetEjectState) -> bool;
+
+  /// Returns the index of the inserted image, starting from 0. An index greater than or equal
+  /// to [`DiskControlCore::get_num_images`] means there's no disk inserted.
+  fn get_image_index(&self, env: &mut impl env::GetImageIndex) -> c_uint;
+
+  /// Selects the image to insert. Only called while the tray is open; an index greater than or
+  /// equal to [`DiskControlCore::get_num_images`] removes the disk. Returns true if successful.
+  fn set_image_index(&mut self, env: &mut impl env::SetImageIndex, index: c_uint) -> bool;
+
+  fn get_num_images(&self, env: &mut impl env::GetNumImages) -> c_uint;
+
+  /// Replaces the image at `index` with `game`, which has the same requirements as the content
+  /// passed to [`Core::load_game`]. If `game` is [`None`], the image is removed and the indices
+  /// of later images shift down by one. Only called while the tray is open.
+  fn replace_image_index(
+    &mut self,
+    env: &mut impl env::ReplaceImageIndex,
+    index: c_uint,
+    game: Option<&GameInfo>,
+  ) -> bool;
+
+  /// Adds an empty slot at the end of the image list, to be filled by
+  /// [`DiskControlCore::replace_image_index`].
+  fn add_image_index(&mut self, env: &mut impl env::AddImageIndex) -> bool;
+
+  /// Called before content is loaded with the image to insert first, usually the one the user
+  /// had inserted last time. The core should remember the choice in `init_state` and use it
+  /// while loading, falling back to the first image if `index` is out of range or the image at
+  /// `index` doesn't have the given path. Returns false if unsupported, which is the default.
+  fn set_initial_image(
+    env: &mut impl env::SetInitialImage,
+    init_state: &mut Self::Init,
+    index: c_uint,
+    path: &CStr,
+  ) -> bool {
+    false
+  }
+
+  /// Returns the path of the image at `index`.
+  fn get_image_path(&self, env: &mut impl env::GetImagePath, index: c_uint) -> Option<&CStr> {
+    None
+  }
+
+  /// Returns a name for the image at `index` to show the user, e.g. its file name without the
+  /// extension, or the name of the disk the game asks for.
+  fn get_image_label(&self, env: &mut impl env::GetImageLabel, index: c_uint) -> Option<&CStr> {
+    None
+  }
+}
+
 /// Functions for getting memory regions (e.g. save RAM.)
 pub trait GetMemoryRegionCore<'a>: Core<'a> {
   fn get_memory_size(&self, env: &mut impl env::GetMemorySize, id: MemoryType) -> usize;
@@ -155,6 +224,54 @@ pub trait RegionAwareCore<'a>: Core<'a> {
   fn get_region(&self, env: &mut impl env::GetRegion) -> Region;
 }
 
+/// Keyboard event handling. The [`libretro_core`] macro registers the keyboard callback during
+/// `retro_init`.
+///
+/// Frontends may report keyboard events from any thread, so events are queued as they arrive
+/// and delivered in order on the thread that calls `retro_run`, just before [`Core::run`].
+/// Events reported while no game is loaded are discarded.
+pub trait KeyboardCore<'a>: Core<'a> {
+  fn keyboard_event(&mut self, env: &mut impl env::KeyboardEvent, event: KeyboardEvent);
+}
+
+/// Camera functions. The camera is requested with [`env::LoadGame::get_camera_interface`],
+/// and its frames are delivered on the thread that calls `retro_run`.
+#[allow(unused_variables)]
+pub trait CameraCore<'a>: Core<'a> {
+  /// Called when the camera is ready to be started.
+  fn camera_initialized(&mut self, env: &mut impl env::CameraInitialized) {}
+
+  /// Called right before the camera is shut down.
+  fn camera_deinitialized(&mut self, env: &mut impl env::CameraDeinitialized) {}
+
+  /// Called with each frame if the core asked for [`CameraOptions::with_raw_framebuffer`].
+  fn camera_frame(
+    &mut self,
+    env: &mut impl env::CameraFrameRawFramebuffer,
+    frame: &Frame<'_, XRGB8888>,
+  ) {
+  }
+
+  /// Called with each frame if the core asked for [`CameraOptions::with_gl_texture`].
+  fn camera_texture(
+    &mut self,
+    env: &mut impl env::CameraFrameOpenGLTexture,
+    texture: &CameraTexture,
+  ) {
+  }
+}
+
+/// Location service notifications. Location services are requested with
+/// [`env::LoadGame::get_location_interface`].
+#[allow(unused_variables)]
+pub trait LocationCore<'a>: Core<'a> {
+  /// Called when location services are ready to be started.
+  fn location_initialized(&mut self, env: &mut impl env::LocationInitialized) {}
+
+  /// Called right before location services are shut down.
+  fn location_deinitialized(&mut self, env: &mut impl env::LocationDeinitialized) {}
+}
+
 /// OpenGL context management functions.
 pub unsafe trait OpenGLCore<'a>: Core<'a> {
   fn context_reset(&mut self, env: &mut impl Environment, callbacks: GLContextCallbacks);
@@ -271,6 +388,55 @@ pub trait Callbacks {
 
   /// Returns true if the specified button is pressed, false otherwise.
   fn is_joypad_button_pressed(&self, port: DevicePort, btn: JoypadButton) -> bool;
+
+  /// Returns the state of every button of the joypad on `port`. This takes a single query if
+  /// the frontend supports input bitmasks, and a query per button otherwise.
+  fn joypad_buttons(&self, port: DevicePort) -> JoypadButtons;
+
+  /// Returns the position of an analog stick of the [`DeviceType::Analog`] on `port`.
+  fn analog_stick(&self, port: DevicePort, stick: AnalogIndex) -> AnalogStick;
+
+  /// Returns how far a button of the [`DeviceType::Analog`] on `port` is pressed, from `0`
+  /// to `0x7fff`. Frontends without analog buttons report `0` or `0x7fff`.
+  fn analog_button(&self, port: DevicePort, button: JoypadButton) -> i16;
+
+  /// Returns the state of the [`DeviceType::Mouse`] on `port`.
+  fn mouse(&self, port: DevicePort) -> MouseState;
+
+  /// Returns the number of touches on the [`DeviceType::Pointer`] on `port`.
+  fn pointer_count(&self, port: DevicePort) -> usize;
+
+  /// Returns the touch with the given index, which is less than [`Callbacks::pointer_count`].
+  fn pointer(&self, port: DevicePort, index: usize) -> PointerState;
+
+  /// Returns the state of the [`DeviceType::LightGun`] on `port`.
+  fn lightgun(&self, port: DevicePort) -> LightgunState;
+
+  /// Returns true if `key` is held down on the [`DeviceType::Keyboard`].
+  fn is_key_pressed(&self, key: RetroKey) -> bool;
+
+  /// Returns every touch on the [`DeviceType::Pointer`] on `port`.
+  ///
+  /// Frontends that can't count touches report none, in which case the first touch is
+  /// returned while it is pressed.
+  fn pointers(&self, port: DevicePort) -> Vec<PointerState> {
+    match self.pointer_count(port) {
+      0 => Some(self.pointer(port, 0))
+        .filter(|pointer| pointer.pressed)
+        .into_iter()
+        .collect(),
+      count => (0..count).map(|index| self.pointer(port, index)).collect(),
+    }
+  }
+
+  /// Returns every key held down on the [`DeviceType::Keyboard`], taking a query per key.
+  fn keyboard(&self) -> KeyboardState {
+    RetroKey::ALL
+      .iter()
+      .fold(KeyboardState::default(), |state, &key| {
+        state.with_key(key, self.is_key_pressed(key))
+      })
+  }
 }
 
 impl Callbacks for InstanceCallbacks {
@@ -312,6 +478,38 @@ impl Callbacks for InstanceCallbacks {
   fn is_joypad_button_pressed(&self, port: DevicePort, btn: JoypadButton) -> bool {
     unsafe { self.is_joypad_button_pressed(port, btn) }
   }
+
+  fn joypad_buttons(&self, port: DevicePort) -> JoypadButtons {
+    unsafe { self.joypad_buttons(port) }
+  }
+
+  fn analog_stick(&self, port: DevicePort, stick: AnalogIndex) -> AnalogStick {
+    unsafe { self.analog_stick(port, stick) }
+  }
+
+  fn analog_button(&self, port: DevicePort, button: JoypadButton) -> i16 {
+    unsafe { self.analog_button(port, button) }
+  }
+
+  fn mouse(&self, port: DevicePort) -> MouseState {
+    unsafe { self.mouse(port) }
+  }
+
+  fn pointer_count(&self, port: DevicePort) -> usize {
+    unsafe { self.pointer_count(port) }
+  }
+
+  fn pointer(&self, port: DevicePort, index: usize) -> PointerState {
+    unsafe { self.pointer(port, index) }
+  }
+
+  fn lightgun(&self, port: DevicePort) -> LightgunState {
+    unsafe { self.lightgun(port) }
+  }
+
+  fn is_key_pressed(&self, key: RetroKey) -> bool {
+    unsafe { self.is_key_pressed(key) }
+  }
 }
 
 pub struct InputsPolled(pub(crate) ());
@@ -340,23 +538,32 @@ impl<'a> Deref for RetroVariable<'a> {
 pub struct Instance<I, C> {
   env: InstanceEnvironment,
   cb: InstanceCallbacks,
+  keyboard: InstanceKeyboardState,
+  disk_control: InstanceDiskControlState,
   init: MaybeUninit<I>,
   core: MaybeUninit<C>,
+  // Whether `core` is initialized. Frontends may invoke some callbacks, such as the camera's,
+  // while no game is loaded.
+  loaded: bool,
 }
 
 impl<I, C> Instance<I, C> {
   pub const fn new(
-    context_reset: non_null_retro_hw_context_reset_t,
-    context_destroy: non_null_retro_hw_context_reset_t,
+    gl: InstanceGLState,
+    options: InstanceOptionsState,
+    camera: InstanceCameraState,
+    location: InstanceLocationState,
+    keyboard: InstanceKeyboardState,
+    disk_control: InstanceDiskControlState,
   ) -> Self {
     Self {
-      env: InstanceEnvironment {
-        cb: None,
-        gl: InstanceGLState::new(context_reset, context_destroy),
-      },
+      env: InstanceEnvironment::new(None, gl, options, camera, location),
       cb: InstanceCallbacks::new(),
+      keyboard,
+      disk_control,
       init: MaybeUninit::uninit(),
       core: MaybeUninit::uninit(),
+      loaded: false,
     }
   }
 
@@ -379,6 +586,18 @@ impl<I, C> Instance<I, C> {
   pub fn on_set_video_refresh(&mut self, cb: non_null_retro_video_refresh_t) {
     self.cb.video_refresh = Some(cb);
   }
+
+  /// Invoked by a `libretro` frontend through the callback registered by
+  /// [`env::SetEnvironment::set_core_options`].
+  ///
+  /// This only evaluates the visibility rules of the core options, so it's safe
+  /// to call whether or not a game is loaded.
+  pub fn on_core_options_update_display(&mut self) -> bool {
+    match self.env.options.update_display {
+      Some(update) => update(&mut self.env),
+      None => false,
+    }
+  }
 }
 
 // The following code exploits the fact that inherent impls can shadow trait
@@ -398,12 +617,18 @@ impl<'a, C: Core<'a>> Instance<C::Init, C> {
   }
 
   pub unsafe fn on_init(&mut self) {
+    self.cb.input_bitmasks = self.env.get_input_bitmasks();
     self.init.write(C::init(&mut self.env));
   }
 
   pub unsafe fn on_load_game(&mut self, game: *const retro_game_info) -> bool {
     let Instance {
-      env, init, core, ..
+      env,
+      keyboard,
+      init,
+      core,
+      loaded,
+      ..
     } = self;
     // Introduce an unbounded lifetime on purpose by coercing to a pointer and back.
     // This is normally extremely dangerous, but the libretro API guarantees that the
@@ -424,6 +649,8 @@ impl<'a, C: Core<'a>> Instance<C::Init, C> {
     match result {
       Ok(system) => {
         core.write(system);
+        *loaded = true;
+        keyboard.events.clear();
         true
       }
       Err(_) => false,
@@ -440,10 +667,14 @@ impl<'a, C: Core<'a>> Instance<C::Init, C> {
   }
 
   pub unsafe fn on_reset(&mut self) {
+    rumble::stop_active(&self.env);
     self.core.assume_init_mut().reset(&mut self.env);
   }
 
   pub unsafe fn on_unload_game(&mut self) {
+    rumble::stop_active(&self.env);
+    self.loaded = false;
+    self.keyboard.events.clear();
     self.core.assume_init_read().unload_game(&mut self.env);
   }
 
@@ -544,6 +775,192 @@ pub trait CheatsCoreFallbacks {
 }
 impl<I, C> CheatsCoreFallbacks for Instance<I, C> {}
 
+impl<'a, C: DiskControlCore<'a>> Instance<C::Init, C> {
+  /// Registers the disk control interface with the frontend. Invoked during `retro_init`.
+  pub unsafe fn on_init_disk_control(&mut self) {
+    let ext = self.disk_control.callbacks;
+    if self.env.get_disk_control_interface_version() >= 1 {
+      let _ = self
+        .env
+        .set(RETRO_ENVIRONMENT_SET_DISK_CONTROL_EXT_INTERFACE, &ext);
+    } else {
+      let data = retro_disk_control_callback {
+        set_eject_state: ext.set_eject_state,
+        get_eject_state: ext.get_eject_state,
+        get_image_index: ext.get_image_index,
+        set_image_index: ext.set_image_index,
+        get_num_images: ext.get_num_images,
+        replace_image_index: ext.replace_image_index,
+        add_image_index: ext.add_image_index,
+      };
+      let _ = self
+        .env
+        .set(RETRO_ENVIRONMENT_SET_DISK_CONTROL_INTERFACE, &data);
+    }
+  }
+
+  pub unsafe fn on_set_eject_state(&mut self, ejected: bool) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_mut().set_eject_state(env, ejected)
+  }
+
+  pub unsafe fn on_get_eject_state(&mut self) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_ref().get_eject_state(env)
+  }
+
+  pub unsafe fn on_get_image_index(&mut self) -> c_uint {
+    if !self.loaded {
+      return 0;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_ref().get_image_index(env)
+  }
+
+  pub unsafe fn on_set_image_index(&mut self, index: c_uint) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_mut().set_image_index(env, index)
+  }
+
+  pub unsafe fn on_get_num_images(&mut self) -> c_uint {
+    if !self.loaded {
+      return 0;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_ref().get_num_images(env)
+  }
+
+  /// # Safety
+  /// `info` must be null or point to a valid [`retro_game_info`].
+  pub unsafe fn on_replace_image_index(
+    &mut self,
+    index: c_uint,
+    info: *const retro_game_info,
+  ) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let lifetime = ();
+    let game = as_ref_with_lifetime(info.cast::<GameInfo>(), &lifetime);
+    let env = &mut self.env;
+    self
+      .core
+      .assume_init_mut()
+      .replace_image_index(env, index, game)
+  }
+
+  pub unsafe fn on_add_image_index(&mut self) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    self.core.assume_init_mut().add_image_index(env)
+  }
+
+  /// Invoked before `retro_load_game`, so this passes the init state instead of the core.
+  ///
+  /// # Safety
+  /// `path` must be null or a valid argument to [`CStr::from_ptr`].
+  pub unsafe fn on_set_initial_image(&mut self, index: c_uint, path: *const c_char) -> bool {
+    if path.is_null() {
+      return false;
+    }
+    let path = CStr::from_ptr(path);
+    C::set_initial_image(&mut self.env, self.init.assume_init_mut(), index, path)
+  }
+
+  /// # Safety
+  /// `path` must point to `len` writable bytes.
+  pub unsafe fn on_get_image_path(&mut self, index: c_uint, path: *mut c_char, len: usize) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    let image_path = self.core.assume_init_ref().get_image_path(env, index);
+    copy_c_str(image_path, path, len)
+  }
+
+  /// # Safety
+  /// `label` must point to `len` writable bytes.
+  pub unsafe fn on_get_image_label(
+    &mut self,
+    index: c_uint,
+    label: *mut c_char,
+    len: usize,
+  ) -> bool {
+    if !self.loaded {
+      return false;
+    }
+    let env = &mut self.env;
+    let image_label = self.core.assume_init_ref().get_image_label(env, index);
+    copy_c_str(image_label, label, len)
+  }
+}
+
+#[doc(hidden)]
+pub trait DiskControlCoreFallbacks {
+  unsafe fn on_init_disk_control(&mut self) {}
+
+  unsafe fn on_set_eject_state(&mut self, _ejected: bool) -> bool {
+    false
+  }
+
+  unsafe fn on_get_eject_state(&mut self) -> bool {
+    false
+  }
+
+  unsafe fn on_get_image_index(&mut self) -> c_uint {
+    0
+  }
+
+  unsafe fn on_set_image_index(&mut self, _index: c_uint) -> bool {
+    false
+  }
+
+  unsafe fn on_get_num_images(&mut self) -> c_uint {
+    0
+  }
+
+  unsafe fn on_replace_image_index(
+    &mut self,
+    _index: c_uint,
+    _info: *const retro_game_info,
+  ) -> bool {
+    false
+  }
+
+  unsafe fn on_add_image_index(&mut self) -> bool {
+    false
+  }
+
+  unsafe fn on_set_initial_image(&mut self, _index: c_uint, _path: *const c_char) -> bool {
+    false
+  }
+
+  unsafe fn on_get_image_path(&mut self, _index: c_uint, _path: *mut c_char, _len: usize) -> bool {
+    false
+  }
+
+  unsafe fn on_get_image_label(
+    &mut self,
+    _index: c_uint,
+    _label: *mut c_char,
+    _len: usize,
+  ) -> bool {
+    false
+  }
+}
+impl<I, C> DiskControlCoreFallbacks for Instance<I, C> {}
+
 impl<'a, C: GetMemoryRegionCore<'a>> Instance<C::Init, C> {
   /// Invoked by a `libretro` frontend, with the `retro_get_memory_data` API call.
   pub unsafe fn on_get_memory_data(&mut self, id: MemoryType) -> *mut () {
@@ -584,7 +1001,12 @@ impl<'a, C: SpecialGameCore<'a>> Instance<C::Init, C> {
     num_info: usize,
   ) -> bool {
     let Instance {
-      env, init, core, ..
+      env,
+      keyboard,
+      init,
+      core,
+      loaded,
+      ..
     } = self;
     // Introduce an unbounded lifetime on purpose by coercing to a pointer and back.
     // This is normally extremely dangerous, but the libretro API guarantees that the
@@ -596,6 +1018,8 @@ impl<'a, C: SpecialGameCore<'a>> Instance<C::Init, C> {
     match result {
       Ok(system) => {
         core.write(system);
+        *loaded = true;
+        keyboard.events.clear();
         true
       }
       Err(_) => false,
@@ -654,16 +1078,227 @@ pub trait OpenGLCoreFallbacks {
 }
 impl<I, C> OpenGLCoreFallbacks for Instance<I, C> {}
 
+impl<'a, C: CameraCore<'a>> Instance<C::Init, C> {
+  pub unsafe fn on_camera_initialized(&mut self) {
+    if !self.loaded {
+      return;
+    }
+    self
+      .core
+      .assume_init_mut()
+      .camera_initialized(&mut self.env);
+  }
+
+  pub unsafe fn on_camera_deinitialized(&mut self) {
+    if !self.loaded {
+      return;
+    }
+    self
+      .core
+      .assume_init_mut()
+      .camera_deinitialized(&mut self.env);
+  }
+
+  /// # Safety
+  /// `buffer` must be null or point to `height` rows of `width` pixels that start `pitch`
+  /// bytes apart.
+  pub unsafe fn on_camera_frame_raw_framebuffer(
+    &mut self,
+    buffer: *const u32,
+    width: c_uint,
+    height: c_uint,
+    pitch: usize,
+  ) {
+    let pitch = (pitch / core::mem::size_of::<u32>()) as u32;
+    if !self.loaded || buffer.is_null() || pitch < width {
+      return;
+    }
+    let len = match height {
+      0 => 0,
+      height => pitch as usize * (height as usize - 1) + width as usize,
+    };
+    let lifetime = ();
+    let data = slice_with_lifetime(buffer.cast::<XRGB8888>(), len, &lifetime);
+    let frame = Frame::new_with_pitch(data, width, height, pitch);
+    self
+      .core
+      .assume_init_mut()
+      .camera_frame(&mut self.env, &frame);
+  }
+
+  /// # Safety
+  /// `affine` must be null or point to 9 floats.
+  pub unsafe fn on_camera_frame_opengl_texture(
+    &mut self,
+    texture_id: c_uint,
+    texture_target: c_uint,
+    affine: *const f32,
+  ) {
+    if !self.loaded {
+      return;
+    }
+    let affine = match affine.cast::<[f32; 9]>().as_ref() {
+      Some(affine) => *affine,
+      None => [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+    };
+    let texture = CameraTexture {
+      id: texture_id,
+      target: texture_target,
+      affine,
+    };
+    self
+      .core
+      .assume_init_mut()
+      .camera_texture(&mut self.env, &texture);
+  }
+}
+
+#[doc(hidden)]
+pub trait CameraCoreFallbacks {
+  unsafe fn on_camera_initialized(&mut self) {}
+
+  unsafe fn on_camera_deinitialized(&mut self) {}
+
+  unsafe fn on_camera_frame_raw_framebuffer(
+    &mut self,
+    _buffer: *const u32,
+    _width: c_uint,
+    _height: c_uint,
+    _pitch: usize,
+  ) {
+  }
+
+  unsafe fn on_camera_frame_opengl_texture(
+    &mut self,
+    _texture_id: c_uint,
+    _texture_target: c_uint,
+    _affine: *const f32,
+  ) {
+  }
+}
+impl<I, C> CameraCoreFallbacks for Instance<I, C> {}
+
+impl<'a, C: LocationCore<'a>> Instance<C::Init, C> {
+  pub unsafe fn on_location_initialized(&mut self) {
+    if !self.loaded {
+      return;
+    }
+    self
+      .core
+      .assume_init_mut()
+      .location_initialized(&mut self.env);
+  }
+
+  pub unsafe fn on_location_deinitialized(&mut self) {
+    if !self.loaded {
+      return;
+    }
+    self
+      .core
+      .assume_init_mut()
+      .location_deinitialized(&mut self.env);
+  }
+}
+
+#[doc(hidden)]
+pub trait LocationCoreFallbacks {
+  unsafe fn on_location_initialized(&mut self) {}
+
+  unsafe fn on_location_deinitialized(&mut self) {}
+}
+impl<I, C> LocationCoreFallbacks for Instance<I, C> {}
+
+impl<'a, C: KeyboardCore<'a>> Instance<C::Init, C> {
+  /// Registers the keyboard callback with the frontend. Invoked during `retro_init`.
+  pub unsafe fn on_init_keyboard(&mut self) {
+    let data = retro_keyboard_callback {
+      callback: Some(self.keyboard.on_event),
+    };
+    let _ = self.env.set(RETRO_ENVIRONMENT_SET_KEYBOARD_CALLBACK, &data);
+  }
+
+  /// Delivers the queued keyboard events to the core. Invoked before `retro_run`.
+  pub unsafe fn on_keyboard_events(&mut self) {
+    let core = self.core.assume_init_mut();
+    for event in self.keyboard.events.take() {
+      core.keyboard_event(&mut self.env, event);
+    }
+  }
+}
+
+#[doc(hidden)]
+pub trait KeyboardCoreFallbacks {
+  unsafe fn on_init_keyboard(&mut self) {}
+
+  unsafe fn on_keyboard_events(&mut self) {}
+}
+impl<I, C> KeyboardCoreFallbacks for Instance<I, C> {}
+
 #[doc(hidden)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct InstanceEnvironment {
   cb: retro_environment_t,
   gl: InstanceGLState,
+  options: InstanceOptionsState,
+  camera: InstanceCameraState,
+  location: InstanceLocationState,
+  // Kept alive for the frontend until they're replaced.
+  pub(crate) input_descriptors: Option<RawInputDescriptors>,
+  pub(crate) controller_info: Option<RawControllerInfo>,
 }
 
 impl InstanceEnvironment {
-  pub const fn new(cb: retro_environment_t, gl: InstanceGLState) -> Self {
-    Self { cb, gl }
+  pub const fn new(
+    cb: retro_environment_t,
+    gl: InstanceGLState,
+    options: InstanceOptionsState,
+    camera: InstanceCameraState,
+    location: InstanceLocationState,
+  ) -> Self {
+    Self {
+      cb,
+      gl,
+      options,
+      camera,
+      location,
+      input_descriptors: None,
+      controller_info: None,
+    }
+  }
+
+  /// Applies the visibility rules of `O`, returning true if any option's visibility changed.
+  pub fn update_core_options_display<O: CoreOptions>(&mut self) -> bool {
+    let visibility =
+      options::visibility(O::DEFINITIONS, |key| self.get_variable(&key).ok().flatten());
+    let visible: Vec<bool> = visibility.iter().map(|(_, visible)| *visible).collect();
+    if visible == self.options.visible {
+      return false;
+    }
+    for (key, visible) in visibility {
+      let _ = self.set_core_option_display(&key, visible);
+    }
+    self.options.visible = visible;
+    true
+  }
+
+  /// Registers a callback through which the frontend has the visibility rules of `O` evaluated
+  /// again, so that its menu reflects changes immediately. The rules are also applied once.
+  ///
+  /// Called by [`env::SetEnvironment::set_core_options`] if any option in `O` has visibility
+  /// rules.
+  pub fn set_core_options_update_display_callback<O: CoreOptions>(&mut self) -> env::Result<()> {
+    self.options.update_display = Some(Self::update_core_options_display::<O>);
+    self.options.visible.clear();
+    self.update_core_options_display::<O>();
+    let data = retro_core_options_update_display_callback {
+      callback: Some(self.options.on_update_display),
+    };
+    unsafe {
+      self.set(
+        RETRO_ENVIRONMENT_SET_CORE_OPTIONS_UPDATE_DISPLAY_CALLBACK,
+        &data,
+      )
+    }
   }
 }
 
@@ -671,6 +1306,10 @@ impl Environment for InstanceEnvironment {
   fn get_ptr(&self) -> non_null_retro_environment_t {
     unsafe { self.cb.unwrap_unchecked() }
   }
+
+  fn instance(&mut self) -> Option<&mut InstanceEnvironment> {
+    Some(self)
+  }
 }
 
 impl env::LoadGame for InstanceEnvironment {
@@ -692,6 +1331,40 @@ impl env::LoadGame for InstanceEnvironment {
     }
     Ok(GLRenderEnabled(()))
   }
+
+  fn get_camera_interface(&mut self, options: CameraOptions) -> env::Result<Camera> {
+    let mut data: retro_camera_callback = options.into();
+    if options.raw_framebuffer() {
+      data.frame_raw_framebuffer = Some(self.camera.frame_raw_framebuffer);
+    }
+    if options.gl_texture() {
+      data.frame_opengl_texture = Some(self.camera.frame_opengl_texture);
+    }
+    data.initialized = Some(self.camera.initialized);
+    data.deinitialized = Some(self.camera.deinitialized);
+    let data: retro_camera_callback =
+      unsafe { self.cmd(RETRO_ENVIRONMENT_GET_CAMERA_INTERFACE, data)? };
+    match (data.start, data.stop) {
+      (Some(start), Some(stop)) => Ok(Camera::new(start, stop)),
+      _ => Err(CommandError::new()),
+    }
+  }
+
+  fn get_location_interface(&mut self) -> env::Result<Location> {
+    let data = retro_location_callback {
+      initialized: Some(self.location.initialized),
+      deinitialized: Some(self.location.deinitialized),
+      ..Default::default()
+    };
+    let data: retro_location_callback =
+      unsafe { self.cmd(RETRO_ENVIRONMENT_GET_LOCATION_INTERFACE, data)? };
+    match (data.start, data.stop, data.get_position, data.set_interval) {
+      (Some(start), Some(stop), Some(get_position), Some(set_interval)) => {
+        Ok(Location::new(start, stop, get_position, set_interval))
+      }
+      _ => Err(CommandError::new()),
+    }
+  }
 }
 
 #[doc(hidden)]
@@ -702,6 +1375,7 @@ pub struct InstanceCallbacks {
   input_poll: retro_input_poll_t,
   input_state: retro_input_state_t,
   video_refresh: retro_video_refresh_t,
+  input_bitmasks: bool,
 }
 
 impl InstanceCallbacks {
@@ -712,6 +1386,7 @@ impl InstanceCallbacks {
       input_poll: None,
       input_state: None,
       video_refresh: None,
+      input_bitmasks: false,
     }
   }
 
@@ -763,6 +1438,109 @@ impl InstanceCallbacks {
     let id = btn.into();
     self.input_state.unwrap_unchecked()(port, device, index, id) != 0
   }
+
+  unsafe fn joypad_buttons(&self, port: DevicePort) -> JoypadButtons {
+    let port = c_uint::from(port.into_inner());
+    let input_state = self.input_state.unwrap_unchecked();
+    if self.input_bitmasks {
+      let mask = input_state(port, RETRO_DEVICE_JOYPAD, 0, JoypadButton::Mask.into());
+      return JoypadButtons::new_with_raw_value(mask as u16);
+    }
+    let mask = (0..u16::BITS)
+      .filter(|&id| input_state(port, RETRO_DEVICE_JOYPAD, 0, id) != 0)
+      .fold(0, |mask, id| mask | (1 << id));
+    JoypadButtons::new_with_raw_value(mask)
+  }
+
+  unsafe fn analog_stick(&self, port: DevicePort, stick: AnalogIndex) -> AnalogStick {
+    let port = c_uint::from(port.into_inner());
+    let input_state = self.input_state.unwrap_unchecked();
+    let index = stick.into();
+    AnalogStick::new(
+      input_state(port, RETRO_DEVICE_ANALOG, index, AnalogAxis::X.into()),
+      input_state(port, RETRO_DEVICE_ANALOG, index, AnalogAxis::Y.into()),
+    )
+  }
+
+  unsafe fn analog_button(&self, port: DevicePort, button: JoypadButton) -> i16 {
+    let port = c_uint::from(port.into_inner());
+    let index = AnalogIndex::Button.into();
+    self.input_state.unwrap_unchecked()(port, RETRO_DEVICE_ANALOG, index, button.into())
+  }
+
+  unsafe fn mouse(&self, port: DevicePort) -> MouseState {
+    let port = c_uint::from(port.into_inner());
+    let input_state = self.input_state.unwrap_unchecked();
+    let axis = |id| input_state(port, RETRO_DEVICE_MOUSE, 0, id);
+    let button = |id| axis(id) != 0;
+    MouseState {
+      x: axis(RETRO_DEVICE_ID_MOUSE_X),
+      y: axis(RETRO_DEVICE_ID_MOUSE_Y),
+      left: button(RETRO_DEVICE_ID_MOUSE_LEFT),
+      right: button(RETRO_DEVICE_ID_MOUSE_RIGHT),
+      middle: button(RETRO_DEVICE_ID_MOUSE_MIDDLE),
+      button_4: button(RETRO_DEVICE_ID_MOUSE_BUTTON_4),
+      button_5: button(RETRO_DEVICE_ID_MOUSE_BUTTON_5),
+      wheel_up: button(RETRO_DEVICE_ID_MOUSE_WHEELUP),
+      wheel_down: button(RETRO_DEVICE_ID_MOUSE_WHEELDOWN),
+      horizontal_wheel_up: button(RETRO_DEVICE_ID_MOUSE_HORIZ_WHEELUP),
+      horizontal_wheel_down: button(RETRO_DEVICE_ID_MOUSE_HORIZ_WHEELDOWN),
+    }
+  }
+
+  unsafe fn pointer_count(&self, port: DevicePort) -> usize {
+    let port = c_uint::from(port.into_inner());
+    let count = self.input_state.unwrap_unchecked()(
+      port,
+      RETRO_DEVICE_POINTER,
+      0,
+      RETRO_DEVICE_ID_POINTER_COUNT,
+    );
+    count.max(0) as usize
+  }
+
+  unsafe fn pointer(&self, port: DevicePort, index: usize) -> PointerState {
+    let port = c_uint::from(port.into_inner());
+    let input_state = self.input_state.unwrap_unchecked();
+    let index = index as c_uint;
+    PointerState {
+      x: input_state(port, RETRO_DEVICE_POINTER, index, RETRO_DEVICE_ID_POINTER_X),
+      y: input_state(port, RETRO_DEVICE_POINTER, index, RETRO_DEVICE_ID_POINTER_Y),
+      pressed: input_state(
+        port,
+        RETRO_DEVICE_POINTER,
+        index,
+        RETRO_DEVICE_ID_POINTER_PRESSED,
+      ) != 0,
+    }
+  }
+  unsafe fn lightgun(&self, port: DevicePort) -> LightgunState {
+    let port = c_uint::from(port.into_inner());
+    let input_state = self.input_state.unwrap_unchecked();
+    let axis = |id| input_state(port, RETRO_DEVICE_LIGHTGUN, 0, id);
+    let button = |id| axis(id) != 0;
+    LightgunState {
+      screen_x: axis(RETRO_DEVICE_ID_LIGHTGUN_SCREEN_X),
+      screen_y: axis(RETRO_DEVICE_ID_LIGHTGUN_SCREEN_Y),
+      is_offscreen: button(RETRO_DEVICE_ID_LIGHTGUN_IS_OFFSCREEN),
+      trigger: button(RETRO_DEVICE_ID_LIGHTGUN_TRIGGER),
+      reload: button(RETRO_DEVICE_ID_LIGHTGUN_RELOAD),
+      aux_a: button(RETRO_DEVICE_ID_LIGHTGUN_AUX_A),
+      aux_b: button(RETRO_DEVICE_ID_LIGHTGUN_AUX_B),
+      aux_c: button(RETRO_DEVICE_ID_LIGHTGUN_AUX_C),
+      start: button(RETRO_DEVICE_ID_LIGHTGUN_START),
+      select: button(RETRO_DEVICE_ID_LIGHTGUN_SELECT),
+      dpad_up: button(RETRO_DEVICE_ID_LIGHTGUN_DPAD_UP),
+      dpad_down: button(RETRO_DEVICE_ID_LIGHTGUN_DPAD_DOWN),
+      dpad_left: button(RETRO_DEVICE_ID_LIGHTGUN_DPAD_LEFT),
+      dpad_right: button(RETRO_DEVICE_ID_LIGHTGUN_DPAD_RIGHT),
+    }
+  }
+
+  unsafe fn is_key_pressed(&self, key: RetroKey) -> bool {
+    // The keyboard isn't tied to a controller port; frontends report it on port 0.
+    self.input_state.unwrap_unchecked()(0, RETRO_DEVICE_KEYBOARD, 0, key.into()) != 0
+  }
 }
 
 #[doc(hidden)]
@@ -786,6 +1564,135 @@ impl InstanceGLState {
   }
 }
 
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceOptionsState {
+  on_update_display: non_null_retro_core_options_update_display_callback_t,
+  update_display: Option<fn(&mut InstanceEnvironment) -> bool>,
+  visible: Vec<bool>,
+}
+
+impl InstanceOptionsState {
+  pub const fn new(
+    on_update_display: non_null_retro_core_options_update_display_callback_t,
+  ) -> Self {
+    Self {
+      on_update_display,
+      update_display: None,
+      visible: Vec::new(),
+    }
+  }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceCameraState {
+  frame_raw_framebuffer: non_null_retro_camera_frame_raw_framebuffer_t,
+  frame_opengl_texture: non_null_retro_camera_frame_opengl_texture_t,
+  initialized: non_null_retro_camera_lifetime_status_t,
+  deinitialized: non_null_retro_camera_lifetime_status_t,
+}
+
+impl InstanceCameraState {
+  pub const fn new(
+    frame_raw_framebuffer: non_null_retro_camera_frame_raw_framebuffer_t,
+    frame_opengl_texture: non_null_retro_camera_frame_opengl_texture_t,
+    initialized: non_null_retro_camera_lifetime_status_t,
+    deinitialized: non_null_retro_camera_lifetime_status_t,
+  ) -> Self {
+    Self {
+      frame_raw_framebuffer,
+      frame_opengl_texture,
+      initialized,
+      deinitialized,
+    }
+  }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceLocationState {
+  initialized: non_null_retro_location_lifetime_status_t,
+  deinitialized: non_null_retro_location_lifetime_status_t,
+}
+
+impl InstanceLocationState {
+  pub const fn new(
+    initialized: non_null_retro_location_lifetime_status_t,
+    deinitialized: non_null_retro_location_lifetime_status_t,
+  ) -> Self {
+    Self {
+      initialized,
+      deinitialized,
+    }
+  }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct InstanceKeyboardState {
+  on_event: non_null_retro_keyboard_event_t,
+  events: &'static KeyboardEventQueue,
+}
+
+impl InstanceKeyboardState {
+  pub const fn new(
+    on_event: non_null_retro_keyboard_event_t,
+    events: &'static KeyboardEventQueue,
+  ) -> Self {
+    Self { on_event, events }
+  }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct InstanceDiskControlState {
+  callbacks: retro_disk_control_ext_callback,
+}
+
+impl InstanceDiskControlState {
+  pub const fn new(callbacks: retro_disk_control_ext_callback) -> Self {
+    Self { callbacks }
+  }
+}
+
+/// Keyboard events waiting to be delivered to a [`KeyboardCore`].
+///
+/// This is the only state the keyboard callback touches, since it may run on any thread.
+/// Events beyond [`KeyboardEventQueue::CAPACITY`] are dropped, so a frontend that reports
+/// events while the core doesn't run can't grow the queue without bound.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct KeyboardEventQueue(Mutex<Vec<KeyboardEvent>>);
+
+impl KeyboardEventQueue {
+  pub const CAPACITY: usize = 1024;
+
+  pub const fn new() -> Self {
+    Self(Mutex::new(Vec::new()))
+  }
+
+  pub fn push(&self, event: KeyboardEvent) {
+    let mut events = self.lock();
+    if events.len() < Self::CAPACITY {
+      events.push(event);
+    }
+  }
+
+  fn clear(&self) {
+    self.lock().clear();
+  }
+
+  fn take(&self) -> Vec<KeyboardEvent> {
+    core::mem::take(&mut *self.lock())
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<KeyboardEvent>> {
+    // A panic while holding the lock can't leave the queue in an inconsistent state.
+    self.0.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
 unsafe fn as_ref_with_lifetime<T>(ptr: *const T, _lifetime: &()) -> Option<&T> {
   ptr.as_ref()
 }
@@ -798,6 +1705,19 @@ unsafe fn slice_with_lifetime_mut<T>(ptr: *mut T, len: usize, _lifetime: &()) ->
   slice::from_raw_parts_mut(ptr, len)
 }
 
+/// Copies `src` into the `len`-byte buffer at `dst`, truncating it if necessary.
+/// Returns false if there's nothing to copy or no room for the nul terminator.
+unsafe fn copy_c_str(src: Option<&CStr>, dst: *mut c_char, len: usize) -> bool {
+  let src = match src {
+    Some(src) if !dst.is_null() && len > 0 => src.to_bytes(),
+    _ => return false,
+  };
+  let copied = src.len().min(len - 1);
+  core::ptr::copy_nonoverlapping(src.as_ptr().cast::<c_char>(), dst, copied);
+  *dst.add(copied) = 0;
+  true
+}
+
 #[macro_export]
 macro_rules! libretro_core {
   ($core:ty) => {
@@ -809,8 +1729,32 @@ macro_rules! libretro_core {
       use libretro_rs::libretro_core;
       use libretro_rs::retro::*;
 
-      static mut RETRO_INSTANCE: Instance<<$core as Core>::Init, $core> =
-        Instance::new(on_context_reset, on_context_destroy);
+      static mut RETRO_INSTANCE: Instance<<$core as Core>::Init, $core> = Instance::new(
+        InstanceGLState::new(on_context_reset, on_context_destroy),
+        InstanceOptionsState::new(on_core_options_update_display),
+        InstanceCameraState::new(
+          on_camera_frame_raw_framebuffer,
+          on_camera_frame_opengl_texture,
+          on_camera_initialized,
+          on_camera_deinitialized,
+        ),
+        InstanceLocationState::new(on_location_initialized, on_location_deinitialized),
+        InstanceKeyboardState::new(on_keyboard_event, &KEYBOARD_EVENTS),
+        InstanceDiskControlState::new(retro_disk_control_ext_callback {
+          set_eject_state: Some(on_set_eject_state),
+          get_eject_state: Some(on_get_eject_state),
+          get_image_index: Some(on_get_image_index),
+          set_image_index: Some(on_set_image_index),
+          get_num_images: Some(on_get_num_images),
+          replace_image_index: Some(on_replace_image_index),
+          add_image_index: Some(on_add_image_index),
+          set_initial_image: Some(on_set_initial_image),
+          get_image_path: Some(on_get_image_path),
+          get_image_label: Some(on_get_image_label),
+        }),
+      );
+
+      static KEYBOARD_EVENTS: KeyboardEventQueue = KeyboardEventQueue::new();
 
       #[no_mangle]
       extern "C" fn retro_api_version() -> c_uint {
@@ -829,7 +1773,9 @@ macro_rules! libretro_core {
 
       #[no_mangle]
       unsafe extern "C" fn retro_init() {
-        RETRO_INSTANCE.on_init()
+        RETRO_INSTANCE.on_init();
+        RETRO_INSTANCE.on_init_keyboard();
+        RETRO_INSTANCE.on_init_disk_control()
       }
 
       #[no_mangle]
@@ -882,6 +1828,7 @@ macro_rules! libretro_core {
 
       #[no_mangle]
       unsafe extern "C" fn retro_run() {
+        RETRO_INSTANCE.on_keyboard_events();
         RETRO_INSTANCE.on_run()
       }
 
@@ -952,6 +1899,100 @@ macro_rules! libretro_core {
       unsafe extern "C" fn on_context_destroy() {
         RETRO_INSTANCE.on_context_destroy()
       }
+
+      unsafe extern "C" fn on_core_options_update_display() -> bool {
+        RETRO_INSTANCE.on_core_options_update_display()
+      }
+
+      unsafe extern "C" fn on_camera_frame_raw_framebuffer(
+        buffer: *const u32,
+        width: c_uint,
+        height: c_uint,
+        pitch: usize,
+      ) {
+        RETRO_INSTANCE.on_camera_frame_raw_framebuffer(buffer, width, height, pitch)
+      }
+
+      unsafe extern "C" fn on_camera_frame_opengl_texture(
+        texture_id: c_uint,
+        texture_target: c_uint,
+        affine: *const f32,
+      ) {
+        RETRO_INSTANCE.on_camera_frame_opengl_texture(texture_id, texture_target, affine)
+      }
+
+      unsafe extern "C" fn on_camera_initialized() {
+        RETRO_INSTANCE.on_camera_initialized()
+      }
+
+      unsafe extern "C" fn on_camera_deinitialized() {
+        RETRO_INSTANCE.on_camera_deinitialized()
+      }
+
+      unsafe extern "C" fn on_location_initialized() {
+        RETRO_INSTANCE.on_location_initialized()
+      }
+
+      unsafe extern "C" fn on_location_deinitialized() {
+        RETRO_INSTANCE.on_location_deinitialized()
+      }
+
+      unsafe extern "C" fn on_set_eject_state(ejected: bool) -> bool {
+        RETRO_INSTANCE.on_set_eject_state(ejected)
+      }
+
+      unsafe extern "C" fn on_get_eject_state() -> bool {
+        RETRO_INSTANCE.on_get_eject_state()
+      }
+
+      unsafe extern "C" fn on_get_image_index() -> c_uint {
+        RETRO_INSTANCE.on_get_image_index()
+      }
+
+      unsafe extern "C" fn on_set_image_index(index: c_uint) -> bool {
+        RETRO_INSTANCE.on_set_image_index(index)
+      }
+
+      unsafe extern "C" fn on_get_num_images() -> c_uint {
+        RETRO_INSTANCE.on_get_num_images()
+      }
+
+      unsafe extern "C" fn on_replace_image_index(
+        index: c_uint,
+        info: *const retro_game_info,
+      ) -> bool {
+        RETRO_INSTANCE.on_replace_image_index(index, info)
+      }
+
+      unsafe extern "C" fn on_add_image_index() -> bool {
+        RETRO_INSTANCE.on_add_image_index()
+      }
+
+      unsafe extern "C" fn on_set_initial_image(index: c_uint, path: *const c_char) -> bool {
+        RETRO_INSTANCE.on_set_initial_image(index, path)
+      }
+
+      unsafe extern "C" fn on_get_image_path(index: c_uint, path: *mut c_char, len: usize) -> bool {
+        RETRO_INSTANCE.on_get_image_path(index, path, len)
+      }
+
+      unsafe extern "C" fn on_get_image_label(
+        index: c_uint,
+        label: *mut c_char,
+        len: usize,
+      ) -> bool {
+        RETRO_INSTANCE.on_get_image_label(index, label, len)
+      }
+
+      // May be called from any thread, so this must not touch RETRO_INSTANCE.
+      unsafe extern "C" fn on_keyboard_event(
+        down: bool,
+        keycode: c_uint,
+        character: u32,
+        key_modifiers: u16,
+      ) {
+        KEYBOARD_EVENTS.push(KeyboardEvent::new(down, keycode, character, key_modifiers))
+      }
     }
   };
 }
@@ -1,4 +1,9 @@
+use crate::ffi::*;
+use crate::retro::av::{Frame, GameGeometry};
 use ::core::ffi::*;
+use bitbybit::bitfield;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
 
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -8,13 +13,25 @@ use ::core::ffi::*;
 pub struct DeviceTypeId(c_uint);
 
 impl DeviceTypeId {
-  pub fn new(id: c_uint) -> Self {
+  pub const fn new(id: c_uint) -> Self {
     Self(id)
   }
 
-  pub fn into_inner(self) -> c_uint {
+  pub const fn into_inner(self) -> c_uint {
     self.0
   }
+
+  /// The standard device type this device is, or is a subclass of.
+  pub const fn base(self) -> DeviceTypeId {
+    Self(self.0 & RETRO_DEVICE_MASK)
+  }
+
+  /// Returns the subclass this ID represents, or [None] for a standard device type.
+  pub fn subclass(self) -> Option<DeviceSubclass> {
+    let id = (self.0 >> RETRO_DEVICE_TYPE_SHIFT).checked_sub(1)?;
+    let base = DeviceType::try_from(self.base()).ok()?;
+    Some(DeviceSubclass::new(base, id))
+  }
 }
 
 impl From<c_uint> for DeviceTypeId {
@@ -42,6 +59,12 @@ pub enum DeviceType {
   Pointer = 6,
 }
 
+impl From<DeviceType> for DeviceTypeId {
+  fn from(device: DeviceType) -> Self {
+    Self::new(device as c_uint)
+  }
+}
+
 impl TryFrom<DeviceTypeId> for DeviceType {
   type Error = ();
 
@@ -59,6 +82,147 @@ impl TryFrom<DeviceTypeId> for DeviceType {
   }
 }
 
+/// A core-specific variant of one of the standard device types, such as a multitap
+/// or a paddle, which the frontend treats as its base type.
+///
+/// Subclasses are advertised with [`ControllerInfo`]. The [`DeviceTypeId`] of a subclass
+/// can be declared as a constant, so cores can match on the IDs they receive:
+///
+/// ```
+/// # use libretro_rs::retro::*;
+/// const MULTITAP: DeviceTypeId = DeviceSubclass::new(DeviceType::Joypad, 0).into_id();
+///
+/// enum Controller {
+///   Joypad,
+///   Multitap,
+/// }
+///
+/// impl TryFrom<DeviceTypeId> for Controller {
+///   type Error = ();
+///
+///   fn try_from(device: DeviceTypeId) -> Result<Self, Self::Error> {
+///     match device {
+///       MULTITAP => Ok(Self::Multitap),
+///       _ if device.base() == DeviceType::Joypad.into() => Ok(Self::Joypad),
+///       _ => Err(()),
+///     }
+///   }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceSubclass {
+  base: DeviceType,
+  id: c_uint,
+}
+
+impl DeviceSubclass {
+  /// Creates the subclass with the given core-specific `id` of `base`.
+  pub const fn new(base: DeviceType, id: c_uint) -> Self {
+    Self { base, id }
+  }
+
+  pub const fn base(self) -> DeviceType {
+    self.base
+  }
+
+  pub const fn id(self) -> c_uint {
+    self.id
+  }
+
+  /// Converts this subclass into the device type ID reported to and by the frontend.
+  pub const fn into_id(self) -> DeviceTypeId {
+    DeviceTypeId::new(((self.id + 1) << RETRO_DEVICE_TYPE_SHIFT) | self.base as c_uint)
+  }
+}
+
+impl From<DeviceSubclass> for DeviceTypeId {
+  fn from(subclass: DeviceSubclass) -> Self {
+    subclass.into_id()
+  }
+}
+
+/// The controllers a core supports on a single port, which the frontend lets the user
+/// choose from. Submitted with [`crate::retro::env::SetEnvironment::set_controller_info`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ControllerInfo(Vec<(DeviceTypeId, &'static CStr)>);
+
+impl ControllerInfo {
+  pub fn new() -> Self {
+    Self(Vec::new())
+  }
+
+  /// Adds a controller. Frontends may keep a pointer to `desc`, so it must be `'static`.
+  pub fn with(mut self, device: impl Into<DeviceTypeId>, desc: &'static CStr) -> Self {
+    self.0.push((device.into(), desc));
+    self
+  }
+}
+
+/// The controllers of every port together with the null-terminated table passed to the
+/// frontend, which borrows the per-port arrays.
+#[derive(Debug)]
+pub(crate) struct RawControllerInfo {
+  info: Vec<retro_controller_info>,
+  _types: Vec<Vec<retro_controller_description>>,
+  ports: Vec<ControllerInfo>,
+}
+
+impl RawControllerInfo {
+  pub fn new(ports: Vec<ControllerInfo>) -> Self {
+    let types: Vec<Vec<_>> = ports
+      .iter()
+      .map(|port| {
+        port
+          .0
+          .iter()
+          .map(|(device, desc)| retro_controller_description {
+            desc: desc.as_ptr(),
+            id: device.into_inner(),
+          })
+          .collect()
+      })
+      .collect();
+    let info = types
+      .iter()
+      .map(|types| retro_controller_info {
+        types: types.as_ptr(),
+        num_types: types.len() as c_uint,
+      })
+      .chain(Some(retro_controller_info::default()))
+      .collect();
+    Self {
+      info,
+      _types: types,
+      ports,
+    }
+  }
+
+  /// The first entry of the table, whose address is passed to the frontend.
+  pub fn as_raw(&self) -> &retro_controller_info {
+    &self.info[0]
+  }
+}
+
+impl Clone for RawControllerInfo {
+  fn clone(&self) -> Self {
+    Self::new(self.ports.clone())
+  }
+}
+
+impl PartialEq for RawControllerInfo {
+  fn eq(&self, other: &Self) -> bool {
+    self.ports == other.ports
+  }
+}
+
+impl Eq for RawControllerInfo {}
+
+impl Hash for RawControllerInfo {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.ports.hash(state)
+  }
+}
+
 /// A libretro device port.
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -108,7 +272,9 @@ pub enum JoypadButton {
   R2 = 13,
   L3 = 14,
   R3 = 15,
-  #[cfg(experimental)]
+  /// Not a button; queries the state of every button at once as a [`JoypadButtons`]
+  /// bitmask. Only valid if [`crate::retro::env::Environment::get_input_bitmasks`]
+  /// returns true.
   Mask = 256,
 }
 
@@ -117,3 +283,529 @@ impl From<JoypadButton> for c_uint {
     button as c_uint
   }
 }
+
+/// The state of every button of a joypad, with a bit per [`JoypadButton`].
+#[bitfield(u16, default: 0)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct JoypadButtons {
+  #[bit(0, rw)]
+  b: bool,
+  #[bit(1, rw)]
+  y: bool,
+  #[bit(2, rw)]
+  select: bool,
+  #[bit(3, rw)]
+  start: bool,
+  #[bit(4, rw)]
+  up: bool,
+  #[bit(5, rw)]
+  down: bool,
+  #[bit(6, rw)]
+  left: bool,
+  #[bit(7, rw)]
+  right: bool,
+  #[bit(8, rw)]
+  a: bool,
+  #[bit(9, rw)]
+  x: bool,
+  #[bit(10, rw)]
+  l1: bool,
+  #[bit(11, rw)]
+  r1: bool,
+  #[bit(12, rw)]
+  l2: bool,
+  #[bit(13, rw)]
+  r2: bool,
+  #[bit(14, rw)]
+  l3: bool,
+  #[bit(15, rw)]
+  r3: bool,
+}
+
+impl JoypadButtons {
+  /// Returns true if `button` is pressed. Always false for [`JoypadButton::Mask`].
+  pub fn is_pressed(&self, button: JoypadButton) -> bool {
+    let bit = button as u32;
+    bit < u16::BITS && self.raw_value() & (1 << bit) != 0
+  }
+
+  /// Returns a copy with `button` set to `pressed`. Ignores [`JoypadButton::Mask`].
+  pub fn with_button(self, button: JoypadButton, pressed: bool) -> Self {
+    let bit = button as u32;
+    if bit >= u16::BITS {
+      return self;
+    }
+    let raw = self.raw_value() & !(1 << bit);
+    Self::new_with_raw_value(raw | (u16::from(pressed) << bit))
+  }
+}
+
+/// The index of an input of [`DeviceType::Analog`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AnalogIndex {
+  #[default]
+  Left = 0,
+  Right = 1,
+  /// The analog value of a button, identified by its [`JoypadButton`].
+  Button = 2,
+}
+
+impl From<AnalogIndex> for c_uint {
+  fn from(index: AnalogIndex) -> c_uint {
+    index as c_uint
+  }
+}
+
+/// An axis of an analog stick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AnalogAxis {
+  #[default]
+  X = 0,
+  Y = 1,
+}
+
+impl From<AnalogAxis> for c_uint {
+  fn from(axis: AnalogAxis) -> c_uint {
+    axis as c_uint
+  }
+}
+
+/// The position of an analog stick. Both axes range from `-0x8000` to `0x7fff`,
+/// with positive values pointing right and down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AnalogStick {
+  pub x: i16,
+  pub y: i16,
+}
+
+impl AnalogStick {
+  /// The largest distance from the center an axis can report.
+  pub const MAX: i16 = 0x7fff;
+
+  pub fn new(x: i16, y: i16) -> Self {
+    Self { x, y }
+  }
+
+  /// The distance from the center, which can exceed [`AnalogStick::MAX`] on the diagonals.
+  pub fn magnitude(&self) -> f32 {
+    f32::hypot(self.x as f32, self.y as f32)
+  }
+
+  /// Centers the stick if it's within `deadzone` of the center, and otherwise rescales
+  /// its position so the edge of the deadzone becomes the center.
+  pub fn with_deadzone(self, deadzone: i16) -> Self {
+    let magnitude = self.magnitude();
+    let deadzone = deadzone.clamp(0, Self::MAX) as f32;
+    if magnitude <= deadzone {
+      return Self::default();
+    }
+    let scale = (magnitude - deadzone) / (Self::MAX as f32 - deadzone) / magnitude;
+    self.scaled(scale * Self::MAX as f32)
+  }
+
+  /// Limits the stick to a circle of radius [`AnalogStick::MAX`], preserving its direction.
+  pub fn clamp_radial(self) -> Self {
+    let magnitude = self.magnitude();
+    if magnitude <= Self::MAX as f32 {
+      return self;
+    }
+    self.scaled(Self::MAX as f32 / magnitude)
+  }
+
+  /// Converts the stick into an 8-way D-pad. Positions within `threshold` of the center
+  /// press no buttons.
+  pub fn to_dpad(&self, threshold: i16) -> JoypadButtons {
+    if self.magnitude() <= threshold.max(0) as f32 {
+      return JoypadButtons::default();
+    }
+    // Split the circle into eight 45 degree sectors centered on each direction.
+    let angle = (self.y as f32).atan2(self.x as f32).to_degrees();
+    let sector = ((angle + 360.0 + 22.5) / 45.0) as u32 % 8;
+    let (right, down, left, up) = match sector {
+      0 => (true, false, false, false),
+      1 => (true, true, false, false),
+      2 => (false, true, false, false),
+      3 => (false, true, true, false),
+      4 => (false, false, true, false),
+      5 => (false, false, true, true),
+      6 => (false, false, false, true),
+      _ => (true, false, false, true),
+    };
+    JoypadButtons::default()
+      .with_right(right)
+      .with_down(down)
+      .with_left(left)
+      .with_up(up)
+  }
+
+  fn scaled(self, scale: f32) -> Self {
+    let axis =
+      |value: i16| (value as f32 * scale).clamp(-Self::MAX as f32, Self::MAX as f32) as i16;
+    Self::new(axis(self.x), axis(self.y))
+  }
+}
+
+/// The state of a [`DeviceType::Mouse`] since the last time inputs were polled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MouseState {
+  /// Horizontal movement in pixels, positive to the right.
+  pub x: i16,
+  /// Vertical movement in pixels, positive downwards.
+  pub y: i16,
+  pub left: bool,
+  pub right: bool,
+  pub middle: bool,
+  pub button_4: bool,
+  pub button_5: bool,
+  pub wheel_up: bool,
+  pub wheel_down: bool,
+  pub horizontal_wheel_up: bool,
+  pub horizontal_wheel_down: bool,
+}
+
+/// A touch, or the mouse cursor, on a [`DeviceType::Pointer`].
+///
+/// The coordinates range from `-0x7fff` at the top left of the screen to `0x7fff` at the
+/// bottom right, and are `-0x8000` while the pointer is outside of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PointerState {
+  pub x: i16,
+  pub y: i16,
+  pub pressed: bool,
+}
+
+impl PointerState {
+  /// Returns true if the pointer is outside of the screen.
+  pub fn is_offscreen(&self) -> bool {
+    self.x == i16::MIN || self.y == i16::MIN
+  }
+
+  /// Converts the coordinates into a pixel of a frame with the base size of `geometry`,
+  /// or [None] if the pointer is outside of the screen.
+  pub fn to_pixel(&self, geometry: &GameGeometry) -> Option<(u16, u16)> {
+    if self.is_offscreen() {
+      return None;
+    }
+    let x = screen_to_pixel(self.x, geometry.base_width().into());
+    let y = screen_to_pixel(self.y, geometry.base_height().into());
+    Some((x as u16, y as u16))
+  }
+}
+
+/// The state of a [`DeviceType::LightGun`].
+///
+/// The screen coordinates range from `-0x7fff` at the top left of the screen to `0x7fff`
+/// at the bottom right.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LightgunState {
+  pub screen_x: i16,
+  pub screen_y: i16,
+  pub is_offscreen: bool,
+  pub trigger: bool,
+  pub reload: bool,
+  pub aux_a: bool,
+  pub aux_b: bool,
+  pub aux_c: bool,
+  pub start: bool,
+  pub select: bool,
+  pub dpad_up: bool,
+  pub dpad_down: bool,
+  pub dpad_left: bool,
+  pub dpad_right: bool,
+}
+
+impl LightgunState {
+  /// Converts the screen coordinates into a position on a raster of the given size,
+  /// or [None] if the light gun is aimed outside of the screen.
+  pub fn to_position(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+    if self.is_offscreen {
+      return None;
+    }
+    let x = screen_to_pixel(self.screen_x, width);
+    let y = screen_to_pixel(self.screen_y, height);
+    Some((x, y))
+  }
+
+  /// Converts the screen coordinates into a pixel of `frame`, or [None] if the
+  /// light gun is aimed outside of the screen.
+  pub fn to_frame_position<T>(&self, frame: &Frame<'_, T>) -> Option<(u32, u32)> {
+    self.to_position(frame.width(), frame.height())
+  }
+}
+
+/// Converts a coordinate from `-0x7fff..=0x7fff` into `0..size`.
+fn screen_to_pixel(coordinate: i16, size: u32) -> u32 {
+  let offset = (coordinate as i64 + 0x7fff).clamp(0, 0xfffe) as u64;
+  let pixel = offset * size as u64 / 0xffff;
+  pixel.min(size.saturating_sub(1) as u64) as u32
+}
+
+/// Describes what the inputs of a core do, so the frontend can display it to the user.
+/// Submitted with [`crate::retro::env::Run::set_input_descriptors`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct InputDescriptors(Vec<InputDescriptor>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct InputDescriptor {
+  port: DevicePort,
+  device: DeviceTypeId,
+  index: c_uint,
+  id: c_uint,
+  description: CString,
+}
+
+impl InputDescriptors {
+  pub fn new() -> Self {
+    Self(Vec::new())
+  }
+
+  /// Describes an input of any device.
+  ///
+  /// # Panics
+  /// Panics if `description` contains a nul byte.
+  pub fn with(
+    mut self,
+    port: DevicePort,
+    device: impl Into<DeviceTypeId>,
+    index: c_uint,
+    id: c_uint,
+    description: impl Into<String>,
+  ) -> Self {
+    self.0.push(InputDescriptor {
+      port,
+      device: device.into(),
+      index,
+      id,
+      description: CString::new(description.into())
+        .expect("input descriptions should not contain nul bytes"),
+    });
+    self
+  }
+
+  /// Describes a button of a [`DeviceType::Joypad`].
+  pub fn with_joypad(
+    self,
+    port: DevicePort,
+    button: JoypadButton,
+    description: impl Into<String>,
+  ) -> Self {
+    self.with(port, DeviceType::Joypad, 0, button.into(), description)
+  }
+
+  /// Describes an axis of an analog stick of a [`DeviceType::Analog`].
+  pub fn with_analog_stick(
+    self,
+    port: DevicePort,
+    stick: AnalogIndex,
+    axis: AnalogAxis,
+    description: impl Into<String>,
+  ) -> Self {
+    self.with(
+      port,
+      DeviceType::Analog,
+      stick.into(),
+      axis.into(),
+      description,
+    )
+  }
+
+  /// Describes the analog value of a button of a [`DeviceType::Analog`].
+  pub fn with_analog_button(
+    self,
+    port: DevicePort,
+    button: JoypadButton,
+    description: impl Into<String>,
+  ) -> Self {
+    let index = AnalogIndex::Button.into();
+    self.with(port, DeviceType::Analog, index, button.into(), description)
+  }
+}
+
+/// [`InputDescriptors`] together with the null-terminated array passed to the frontend, which
+/// borrows the descriptions.
+#[derive(Debug)]
+pub(crate) struct RawInputDescriptors {
+  raw: Vec<retro_input_descriptor>,
+  descriptors: InputDescriptors,
+}
+
+impl RawInputDescriptors {
+  pub fn new(descriptors: InputDescriptors) -> Self {
+    let raw = descriptors
+      .0
+      .iter()
+      .map(|descriptor| retro_input_descriptor {
+        port: descriptor.port.into(),
+        device: descriptor.device.into(),
+        index: descriptor.index,
+        id: descriptor.id,
+        description: descriptor.description.as_ptr(),
+      })
+      .chain(Some(retro_input_descriptor::default()))
+      .collect();
+    Self { raw, descriptors }
+  }
+
+  /// The first entry of the array, whose address is passed to the frontend.
+  pub fn as_raw(&self) -> &retro_input_descriptor {
+    &self.raw[0]
+  }
+}
+
+impl Clone for RawInputDescriptors {
+  fn clone(&self) -> Self {
+    Self::new(self.descriptors.clone())
+  }
+}
+
+impl PartialEq for RawInputDescriptors {
+  fn eq(&self, other: &Self) -> bool {
+    self.descriptors == other.descriptors
+  }
+}
+
+impl Eq for RawInputDescriptors {}
+
+impl Hash for RawInputDescriptors {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.descriptors.hash(state)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MAX: i16 = AnalogStick::MAX;
+
+  fn dpad(x: i16, y: i16) -> (bool, bool, bool, bool) {
+    let buttons = AnalogStick::new(x, y).to_dpad(1000);
+    (
+      buttons.right(),
+      buttons.down(),
+      buttons.left(),
+      buttons.up(),
+    )
+  }
+
+  #[test]
+  fn deadzone() {
+    let deadzone = 0x2000;
+    assert_eq!(
+      AnalogStick::new(0x1000, -0x1000).with_deadzone(deadzone),
+      AnalogStick::default()
+    );
+    assert_eq!(
+      AnalogStick::new(MAX, 0).with_deadzone(deadzone),
+      AnalogStick::new(MAX, 0)
+    );
+    // Halfway between the edge of the deadzone and the edge of the stick.
+    let halfway = deadzone + (MAX - deadzone) / 2;
+    let stick = AnalogStick::new(0, -halfway).with_deadzone(deadzone);
+    assert_eq!(stick.x, 0);
+    assert!((stick.y + MAX / 2).abs() <= 1, "{stick:?}");
+    // The most negative value is clamped to the range of the positive axis.
+    assert_eq!(
+      AnalogStick::new(i16::MIN, 0).with_deadzone(0),
+      AnalogStick::new(-MAX, 0)
+    );
+  }
+
+  #[test]
+  fn clamp_radial() {
+    let stick = AnalogStick::new(100, -200);
+    assert_eq!(stick.clamp_radial(), stick);
+    assert_eq!(
+      AnalogStick::new(i16::MIN, 0).clamp_radial(),
+      AnalogStick::new(-MAX, 0)
+    );
+    let stick = AnalogStick::new(MAX, -MAX).clamp_radial();
+    assert_eq!(stick.x, -stick.y);
+    assert!(stick.magnitude() <= MAX as f32);
+    assert!(stick.magnitude() >= MAX as f32 - 2.0, "{stick:?}");
+  }
+
+  #[test]
+  fn dpad_sectors() {
+    assert_eq!(dpad(500, 500), (false, false, false, false));
+    assert_eq!(dpad(MAX, 0), (true, false, false, false));
+    assert_eq!(dpad(MAX, MAX), (true, true, false, false));
+    assert_eq!(dpad(0, MAX), (false, true, false, false));
+    assert_eq!(dpad(-MAX, MAX), (false, true, true, false));
+    assert_eq!(dpad(-MAX, 0), (false, false, true, false));
+    assert_eq!(dpad(i16::MIN, i16::MIN), (false, false, true, true));
+    assert_eq!(dpad(0, i16::MIN), (false, false, false, true));
+    assert_eq!(dpad(MAX, -MAX), (true, false, false, true));
+    // Sectors are 45 degrees wide, so diagonals start 22.5 degrees from each axis.
+    assert_eq!(dpad(10000, 3640), (true, false, false, false)); // 20 degrees
+    assert_eq!(dpad(10000, 4663), (true, true, false, false)); // 25 degrees
+    assert_eq!(dpad(-3640, -10000), (false, false, false, true)); // 20 degrees from up
+    assert_eq!(dpad(-4663, -10000), (false, false, true, true)); // 25 degrees from up
+  }
+
+  #[test]
+  fn pointer_to_pixel() {
+    let geometry = GameGeometry::new(320..=320, 240..=240, 0.0);
+    let pixel = |x, y| {
+      PointerState {
+        x,
+        y,
+        pressed: true,
+      }
+      .to_pixel(&geometry)
+    };
+    assert_eq!(pixel(-0x7fff, -0x7fff), Some((0, 0)));
+    assert_eq!(pixel(0, 0), Some((159, 119)));
+    assert_eq!(pixel(0x7fff, 0x7fff), Some((319, 239)));
+    assert_eq!(pixel(i16::MIN, 0), None);
+    assert_eq!(pixel(0, i16::MIN), None);
+  }
+
+  #[test]
+  fn lightgun_to_position() {
+    let lightgun = |screen_x, screen_y| LightgunState {
+      screen_x,
+      screen_y,
+      ..LightgunState::default()
+    };
+    assert_eq!(
+      lightgun(-0x7fff, -0x7fff).to_position(320, 240),
+      Some((0, 0))
+    );
+    assert_eq!(lightgun(0, 0).to_position(320, 240), Some((159, 119)));
+    assert_eq!(
+      lightgun(0x7fff, 0x7fff).to_position(320, 240),
+      Some((319, 239))
+    );
+    // The most negative value is treated as the left or top edge.
+    assert_eq!(lightgun(i16::MIN, 0).to_position(320, 240), Some((0, 119)));
+    let offscreen = LightgunState {
+      is_offscreen: true,
+      ..lightgun(0, 0)
+    };
+    assert_eq!(offscreen.to_position(320, 240), None);
+
+    let data = [[0u16; 4]; 3];
+    let frame = Frame::from_2d_array(&data);
+    assert_eq!(
+      lightgun(-0x7fff, -0x7fff).to_frame_position(&frame),
+      Some((0, 0))
+    );
+    assert_eq!(lightgun(0, 0).to_frame_position(&frame), Some((1, 1)));
+    assert_eq!(
+      lightgun(0x7fff, 0x7fff).to_frame_position(&frame),
+      Some((3, 2))
+    );
+    assert_eq!(offscreen.to_frame_position(&frame), None);
+  }
+
+  #[test]
+  fn screen_to_pixel_edges() {
+    assert_eq!(screen_to_pixel(-0x7fff, 320), 0);
+    assert_eq!(screen_to_pixel(i16::MIN, 320), 0);
+    assert_eq!(screen_to_pixel(0, 320), 159);
+    assert_eq!(screen_to_pixel(0x7fff, 320), 319);
+    assert_eq!(screen_to_pixel(0x7fff, 1), 0);
+    assert_eq!(screen_to_pixel(0, 0), 0);
+  }
+}
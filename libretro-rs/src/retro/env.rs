@@ -1,5 +1,7 @@
 pub use crate::convert::*;
 use crate::ffi::*;
+use crate::retro::device::{RawControllerInfo, RawInputDescriptors};
+use crate::retro::options::{CoreOptionsV1, CoreOptionsV2, Variables};
 use crate::retro::pixel::{Format, ORGB1555, RGB565, XRGB8888};
 use crate::retro::*;
 use libretro_rs_ffi::retro_pixel_format::{
@@ -65,6 +67,13 @@ pub trait Environment: Sized {
     with_mut(self.get_ptr(), cmd.into(), &mut data).map(|_| data)
   }
 
+  /// The environment passed to cores by [`libretro_core!`](crate::libretro_core), which holds
+  /// the state that some commands need to register callbacks or keep data alive.
+  #[doc(hidden)]
+  fn instance(&mut self) -> Option<&mut InstanceEnvironment> {
+    None
+  }
+
   /// Sets screen rotation of graphics.
   fn set_rotation(&mut self, rotation: ScreenRotation) -> Result<()> {
     unsafe { self.set(RETRO_ENVIRONMENT_SET_ROTATION, &(rotation as c_int)) }
@@ -77,6 +86,19 @@ pub trait Environment: Sized {
     unsafe { self.get(RETRO_ENVIRONMENT_GET_OVERSCAN) }
   }
 
+  /// Returns true if the frontend can report the state of every joypad button at once
+  /// through [JoypadButton::Mask].
+  fn get_input_bitmasks(&self) -> bool {
+    // Frontends answer through the return value and may not write to the data at all.
+    unsafe { self.get::<_, bool>(RETRO_ENVIRONMENT_GET_INPUT_BITMASKS) }.is_ok()
+  }
+
+  /// Returns the version of the disk control interface the frontend supports. Version 0 means
+  /// only the legacy interface is supported.
+  fn get_disk_control_interface_version(&self) -> c_uint {
+    unsafe { self.get(RETRO_ENVIRONMENT_GET_DISK_CONTROL_INTERFACE_VERSION) }.unwrap_or(0)
+  }
+
   /// Boolean value indicating whether or not frontend supports frame duping.
   fn get_can_dupe(&self) -> Result<bool> {
     unsafe { self.get(RETRO_ENVIRONMENT_GET_CAN_DUPE) }
@@ -137,6 +159,67 @@ pub trait Environment: Sized {
     }
   }
 
+  /// Queries the current value of every option in `O`. Options that the frontend
+  /// doesn't report, or reports with an invalid value, take their default value.
+  fn get_core_options<O: CoreOptions>(&self) -> O {
+    O::from_values(|key| self.get_variable(&key).ok().flatten())
+  }
+
+  /// Notifies the frontend that the core changed the value of a core option.
+  ///
+  /// `value` must be one of the values the option was declared with; prefer
+  /// [Environment::set_core_option], which checks this.
+  fn set_variable(&mut self, key: &impl AsRef<CStr>, value: &impl AsRef<CStr>) -> Result<()> {
+    let variable = retro_variable {
+      key: key.as_ref().as_ptr(),
+      value: value.as_ref().as_ptr(),
+    };
+    unsafe { self.set(RETRO_ENVIRONMENT_SET_VARIABLE, &variable) }
+  }
+
+  /// Changes the value of a core option, e.g. `env.set_core_option(&OptionsChange::Speed(10))`.
+  /// The change is reported by [Run::get_core_options_update] like any other.
+  ///
+  /// Returns [SetCoreOptionError::InvalidValue] without contacting the frontend if the new value
+  /// isn't one of the values the option was declared with.
+  fn set_core_option(
+    &mut self,
+    change: &impl OptionChange,
+  ) -> core::result::Result<(), SetCoreOptionError> {
+    let definition = change.definition();
+    let value = change.value();
+    if !definition.has_value(&value) {
+      return Err(SetCoreOptionError::InvalidValue);
+    }
+    Ok(self.set_variable(&definition.key(), &value)?)
+  }
+
+  /// Suggests that the frontend show or hide the core option with the given key in its menu.
+  fn set_core_option_display(&mut self, key: &impl AsRef<CStr>, visible: bool) -> Result<()> {
+    let display = retro_core_option_display {
+      key: key.as_ref().as_ptr(),
+      visible,
+    };
+    unsafe { self.set(RETRO_ENVIRONMENT_SET_CORE_OPTIONS_DISPLAY, &display) }
+  }
+
+  /// Shows or hides every option in `O` with visibility rules, according to the current values
+  /// of the options they depend on.
+  fn set_core_options_display<O: CoreOptions>(&mut self) {
+    let visibility =
+      options::visibility(O::DEFINITIONS, |key| self.get_variable(&key).ok().flatten());
+    for (key, visible) in visibility {
+      let _ = self.set_core_option_display(&key, visible);
+    }
+  }
+
+  /// Queries the language of the frontend's user interface.
+  /// Returns an error if the frontend reports a language unknown to this crate.
+  fn get_language(&self) -> Result<Language> {
+    let language: c_uint = unsafe { self.get(RETRO_ENVIRONMENT_GET_LANGUAGE)? };
+    Language::try_from(language).map_err(|_| CommandError::new())
+  }
+
   /// Queries the username associated with the frontend.
   fn get_username(&self) -> Result<Option<&CStr>> {
     unsafe { self.get(RETRO_ENVIRONMENT_GET_USERNAME).unsafe_into() }
@@ -149,6 +232,102 @@ pub trait Environment: Sized {
   fn get_log_interface(&self) -> Result<PlatformLogger> {
     unsafe { self.get(RETRO_ENVIRONMENT_GET_LOG_INTERFACE).unsafe_into() }
   }
+
+  /// Gets an interface for controlling the rumble motors of controllers.
+  fn get_rumble_interface(&self) -> Result<Rumble> {
+    let data: retro_rumble_interface = unsafe { self.get(RETRO_ENVIRONMENT_GET_RUMBLE_INTERFACE)? };
+    data
+      .set_rumble_state
+      .map(Rumble::new)
+      .ok_or_else(CommandError::new)
+  }
+
+  /// Gets an interface for turning the frontend's drive and power lights on and off.
+  fn get_led_interface(&self) -> Result<Led> {
+    let data: retro_led_interface = unsafe { self.get(RETRO_ENVIRONMENT_GET_LED_INTERFACE)? };
+    data
+      .set_led_state
+      .map(Led::new)
+      .ok_or_else(CommandError::new)
+  }
+
+  /// Gets an interface for reading and writing raw MIDI data.
+  fn get_midi_interface(&self) -> Result<Midi> {
+    let data: retro_midi_interface = unsafe { self.get(RETRO_ENVIRONMENT_GET_MIDI_INTERFACE)? };
+    match (
+      data.input_enabled,
+      data.output_enabled,
+      data.read,
+      data.write,
+      data.flush,
+    ) {
+      (Some(input_enabled), Some(output_enabled), Some(read), Some(write), Some(flush)) => {
+        Ok(Midi::new(input_enabled, output_enabled, read, write, flush))
+      }
+      _ => Err(CommandError::new()),
+    }
+  }
+
+  /// Gets an interface for timers, CPU feature detection and performance counters.
+  fn get_perf_interface(&self) -> Result<Perf> {
+    let data: retro_perf_callback = unsafe { self.get(RETRO_ENVIRONMENT_GET_PERF_INTERFACE)? };
+    match (
+      data.get_time_usec,
+      data.get_cpu_features,
+      data.get_perf_counter,
+      data.perf_register,
+      data.perf_start,
+      data.perf_stop,
+      data.perf_log,
+    ) {
+      (
+        Some(get_time_usec),
+        Some(get_cpu_features),
+        Some(get_perf_counter),
+        Some(perf_register),
+        Some(perf_start),
+        Some(perf_stop),
+        Some(perf_log),
+      ) => Ok(Perf::new(
+        get_time_usec,
+        get_cpu_features,
+        get_perf_counter,
+        perf_register,
+        perf_start,
+        perf_stop,
+        perf_log,
+      )),
+      _ => Err(CommandError::new()),
+    }
+  }
+
+  /// Gets the frontend's virtual file system, asking for the newest version of the interface
+  /// the frontend supports. Falls back to [`std::fs`] if the frontend doesn't provide one.
+  ///
+  /// Frontends only hand out VFS paths to cores that ask for the interface before loading
+  /// content, so this should be called during [`SetEnvironment`].
+  fn get_vfs_interface(&self) -> Vfs {
+    (1..=Vfs::MAX_VERSION)
+      .rev()
+      .find_map(|version| {
+        let info = retro_vfs_interface_info {
+          required_interface_version: version,
+          iface: std::ptr::null_mut(),
+        };
+        let info: retro_vfs_interface_info =
+          unsafe { self.get_with(RETRO_ENVIRONMENT_GET_VFS_INTERFACE, info) }.ok()?;
+        let iface = std::ptr::NonNull::new(info.iface)?;
+        Some(unsafe { Vfs::new(iface, info.required_interface_version) })
+      })
+      .unwrap_or_default()
+  }
+
+  /// Gets an interface for the accelerometer, gyroscope and light sensors of the device.
+  fn get_sensor_interface(&self) -> Result<Sensors> {
+    let data: retro_sensor_interface = unsafe { self.get(RETRO_ENVIRONMENT_GET_SENSOR_INTERFACE)? };
+    let set_sensor_state = data.set_sensor_state.ok_or_else(CommandError::new)?;
+    Ok(Sensors::new(set_sensor_state, data.get_sensor_input))
+  }
 }
 
 impl Environment for non_null_retro_environment_t {
@@ -161,6 +340,51 @@ pub trait SetEnvironment: Environment {
   fn set_support_no_game(&mut self, data: bool) -> Result<()> {
     unsafe { self.set(RETRO_ENVIRONMENT_SET_SUPPORT_NO_GAME, &data) }
   }
+
+  /// Advertises the controllers the core supports on each port, starting with port 0.
+  ///
+  /// Frontends may read the controllers at any time, so they're kept alive until they're
+  /// replaced. Only the environment passed to cores can do that; other environments return an
+  /// error without contacting the frontend.
+  fn set_controller_info(&mut self, ports: &[ControllerInfo]) -> Result<()> {
+    let env = self.instance().ok_or_else(CommandError::new)?;
+    let info = RawControllerInfo::new(ports.to_vec());
+    unsafe { env.set(RETRO_ENVIRONMENT_SET_CONTROLLER_INFO, info.as_raw())? };
+    env.controller_info = Some(info);
+    Ok(())
+  }
+
+  /// Queries the version of the core options interface supported by the frontend.
+  /// Frontends that predate the query only support `RETRO_ENVIRONMENT_SET_VARIABLES`.
+  fn get_core_options_version(&self) -> Result<c_uint> {
+    unsafe { self.get(RETRO_ENVIRONMENT_GET_CORE_OPTIONS_VERSION) }
+  }
+
+  /// Declares the core options in `O` to the frontend, which then displays them to the user.
+  /// Their values can be read back with [Environment::get_core_options].
+  ///
+  /// The options are downgraded to the newest interface the frontend supports. Categories are
+  /// flattened for frontends without `RETRO_ENVIRONMENT_SET_CORE_OPTIONS_V2`, and labels and
+  /// information are dropped for frontends that only support `RETRO_ENVIRONMENT_SET_VARIABLES`.
+  ///
+  /// If [CoreOptions::TRANSLATIONS] has a translation for the frontend's language, it's
+  /// submitted alongside the English options, which remain the fallback for missing strings.
+  ///
+  /// Options with visibility rules, set with [OptionDefinition::with_visible_if], are shown or
+  /// hidden according to the values of the options they depend on.
+  fn set_core_options<O: CoreOptions>(&mut self) -> Result<()> {
+    submit_core_options::<O>(self)?;
+    if O::DEFINITIONS.iter().any(|d| !d.visible_if().is_empty()) {
+      // Frontends without the callback still get updates from Run::get_core_options_update.
+      match self.instance() {
+        Some(env) => {
+          let _ = env.set_core_options_update_display_callback::<O>();
+        }
+        None => self.set_core_options_display::<O>(),
+      }
+    }
+    Ok(())
+  }
 }
 impl<T: Environment> SetEnvironment for T {}
 
@@ -182,6 +406,49 @@ pub trait Run: Environment {
   fn set_geometry(&mut self, geometry: &GameGeometry) -> Result<()> {
     unsafe { self.set(RETRO_ENVIRONMENT_SET_GEOMETRY, geometry) }
   }
+
+  /// Describes the inputs the core uses to the frontend, replacing any previous descriptors.
+  ///
+  /// Frontends may read the descriptors at any time, so they're kept alive until they're
+  /// replaced. Only the environment passed to cores can do that; other environments return an
+  /// error without contacting the frontend.
+  fn set_input_descriptors(&mut self, descriptors: InputDescriptors) -> Result<()> {
+    let env = self.instance().ok_or_else(CommandError::new)?;
+    let descriptors = RawInputDescriptors::new(descriptors);
+    unsafe {
+      env.set(
+        RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+        descriptors.as_raw(),
+      )?
+    };
+    env.input_descriptors = Some(descriptors);
+    Ok(())
+  }
+
+  /// Boolean value indicating whether or not the user changed any core options since the last
+  /// call. The values can then be read with [Environment::get_variable].
+  fn get_variable_update(&self) -> Result<bool> {
+    unsafe { self.get(RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE) }
+  }
+
+  /// Re-reads `options` if the user changed any core options since the last call, and returns
+  /// the options whose parsed values changed. Returns an empty [Vec] if nothing changed.
+  ///
+  /// Also re-evaluates the visibility rules of `O` when something changed.
+  fn get_core_options_update<O: CoreOptions>(&mut self, options: &mut O) -> Vec<O::Change> {
+    match self.get_variable_update() {
+      Ok(true) => {
+        match self.instance() {
+          Some(env) => {
+            env.update_core_options_display::<O>();
+          }
+          None => self.set_core_options_display::<O>(),
+        }
+        options.update(self.get_core_options())
+      }
+      _ => Vec::new(),
+    }
+  }
 }
 impl<T: Environment> Run for T {}
 
@@ -219,6 +486,10 @@ pub trait LoadGame: Environment {
     }
   }
 
+  fn set_input_descriptors(&mut self, descriptors: InputDescriptors) -> Result<()> {
+    Run::set_input_descriptors(self, descriptors)
+  }
+
   fn set_pixel_format_0rgb1555<F>(
     &mut self,
     current_format: Format<F>,
@@ -243,6 +514,14 @@ pub trait LoadGame: Environment {
   fn set_hw_render_none(&mut self) -> Result<()>;
 
   fn set_hw_render_gl(&mut self, options: GLOptions) -> Result<GLRenderEnabled>;
+
+  /// Gets access to a camera. Frames and the camera's lifetime notifications are delivered
+  /// to the core through [`CameraCore`].
+  fn get_camera_interface(&mut self, options: CameraOptions) -> Result<Camera>;
+
+  /// Gets access to location services. The location service's lifetime notifications are
+  /// delivered to the core through [`LocationCore`].
+  fn get_location_interface(&mut self) -> Result<Location>;
 }
 
 #[non_exhaustive]
@@ -322,6 +601,100 @@ impl<T: Environment> GetMemorySize for T {}
 pub trait Deinit: Environment {}
 impl<T: Environment> Deinit for T {}
 
+pub trait KeyboardEvent: Environment {}
+impl<T: Environment> KeyboardEvent for T {}
+
+pub trait SetEjectState: Environment {}
+impl<T: Environment> SetEjectState for T {}
+
+pub trait GetEjectState: Environment {}
+impl<T: Environment> GetEjectState for T {}
+
+pub trait GetImageIndex: Environment {}
+impl<T: Environment> GetImageIndex for T {}
+
+pub trait SetImageIndex: Environment {}
+impl<T: Environment> SetImageIndex for T {}
+
+pub trait GetNumImages: Environment {}
+impl<T: Environment> GetNumImages for T {}
+
+pub trait ReplaceImageIndex: Environment {}
+impl<T: Environment> ReplaceImageIndex for T {}
+
+pub trait AddImageIndex: Environment {}
+impl<T: Environment> AddImageIndex for T {}
+
+pub trait SetInitialImage: Environment {}
+impl<T: Environment> SetInitialImage for T {}
+
+pub trait GetImagePath: Environment {}
+impl<T: Environment> GetImagePath for T {}
+
+pub trait GetImageLabel: Environment {}
+impl<T: Environment> GetImageLabel for T {}
+
+pub trait CameraInitialized: Environment {}
+impl<T: Environment> CameraInitialized for T {}
+
+pub trait CameraDeinitialized: Environment {}
+impl<T: Environment> CameraDeinitialized for T {}
+
+pub trait CameraFrameRawFramebuffer: Environment {}
+impl<T: Environment> CameraFrameRawFramebuffer for T {}
+
+pub trait CameraFrameOpenGLTexture: Environment {}
+impl<T: Environment> CameraFrameOpenGLTexture for T {}
+
+pub trait LocationInitialized: Environment {}
+impl<T: Environment> LocationInitialized for T {}
+
+pub trait LocationDeinitialized: Environment {}
+impl<T: Environment> LocationDeinitialized for T {}
+
+/// Submits the options in `O` through the newest interface the frontend supports.
+fn submit_core_options<O: CoreOptions>(env: &mut impl SetEnvironment) -> Result<()> {
+  let translation =
+    OptionsTranslation::find(O::TRANSLATIONS, env.get_language().unwrap_or_default());
+  match env.get_core_options_version().unwrap_or(0) {
+    0 => {
+      let variables = Variables::new(O::DEFINITIONS, translation);
+      unsafe { env.set(RETRO_ENVIRONMENT_SET_VARIABLES, variables.as_raw()) }
+    }
+    1 => {
+      let mut us = CoreOptionsV1::new(O::DEFINITIONS);
+      if let Some(translation) = translation {
+        let mut local = CoreOptionsV1::new(translation.definitions());
+        let intl = retro_core_options_intl {
+          us: us.as_mut_ptr(),
+          local: local.as_mut_ptr(),
+        };
+        if unsafe { env.set(RETRO_ENVIRONMENT_SET_CORE_OPTIONS_INTL, &intl) }.is_ok() {
+          return Ok(());
+        }
+      }
+      unsafe { env.set(RETRO_ENVIRONMENT_SET_CORE_OPTIONS, us.as_raw()) }
+    }
+    _ => {
+      let mut us = CoreOptionsV2::new(O::CATEGORIES, O::DEFINITIONS);
+      // The frontend reports success only if it supports categories, which doesn't matter here.
+      let _ = match translation {
+        Some(translation) => {
+          let mut local = CoreOptionsV2::new(translation.categories(), translation.definitions());
+          let (mut us, mut local) = (us.as_raw(), local.as_raw());
+          let intl = retro_core_options_v2_intl {
+            us: &mut us,
+            local: &mut local,
+          };
+          unsafe { env.set(RETRO_ENVIRONMENT_SET_CORE_OPTIONS_V2_INTL, &intl) }
+        }
+        None => unsafe { env.set(RETRO_ENVIRONMENT_SET_CORE_OPTIONS_V2, &us.as_raw()) },
+      };
+      Ok(())
+    }
+  }
+}
+
 unsafe fn with_ref(
   cb: non_null_retro_environment_t,
   cmd: c_uint,
@@ -367,12 +740,31 @@ impl CommandData for c_uint {}
 impl CommandData for Option<&c_char> {}
 impl CommandData for Option<&c_void> {}
 impl CommandData for retro_hw_render_callback {}
+impl CommandData for retro_camera_callback {}
+impl CommandData for retro_controller_info {}
+impl CommandData for retro_disk_control_callback {}
+impl CommandData for retro_disk_control_ext_callback {}
 impl CommandData for retro_game_geometry {}
+impl CommandData for retro_input_descriptor {}
+impl CommandData for retro_keyboard_callback {}
 impl CommandData for GameGeometry {}
+impl CommandData for retro_led_interface {}
+impl CommandData for retro_location_callback {}
 impl CommandData for retro_log_callback {}
+impl CommandData for retro_rumble_interface {}
+impl CommandData for retro_sensor_interface {}
 impl CommandData for retro_message {}
+impl CommandData for retro_midi_interface {}
 impl CommandData for Message {}
+impl CommandData for retro_perf_callback {}
 impl CommandData for retro_pixel_format {}
 impl CommandData for retro_system_av_info {}
 impl CommandData for SystemAVInfo {}
 impl CommandData for retro_variable {}
+impl CommandData for retro_vfs_interface_info {}
+impl CommandData for retro_core_option_definition {}
+impl CommandData for retro_core_option_display {}
+impl CommandData for retro_core_options_update_display_callback {}
+impl CommandData for retro_core_options_intl {}
+impl CommandData for retro_core_options_v2 {}
+impl CommandData for retro_core_options_v2_intl {}
@@ -68,6 +68,38 @@ impl From<CommandError> for CoreError {
   }
 }
 
+/// The error returned by [`crate::retro::env::Environment::set_core_option`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SetCoreOptionError {
+  /// The new value isn't one of the values the option was declared with.
+  InvalidValue,
+  /// The frontend rejected the value, or doesn't support `RETRO_ENVIRONMENT_SET_VARIABLE`.
+  Command(CommandError),
+}
+
+impl Display for SetCoreOptionError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::InvalidValue => write!(f, "the value is not one of the core option's values"),
+      Self::Command(error) => Display::fmt(error, f),
+    }
+  }
+}
+
+impl Error for SetCoreOptionError {}
+
+impl From<CommandError> for SetCoreOptionError {
+  fn from(value: CommandError) -> Self {
+    Self::Command(value)
+  }
+}
+
+impl From<SetCoreOptionError> for CoreError {
+  fn from(_value: SetCoreOptionError) -> Self {
+    Self::new()
+  }
+}
+
 impl<T> From<crate::retro::av::pixel::Format<T>> for CoreError {
   fn from(_value: crate::retro::av::pixel::Format<T>) -> Self {
     Self::new()
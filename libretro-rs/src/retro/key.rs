@@ -0,0 +1,554 @@
+use ::core::convert::Infallible;
+use ::core::fmt::{Display, Formatter};
+use bitbybit::bitfield;
+use std::error::Error;
+use std::ffi::c_uint;
+
+/// The keys a frontend can report, mirroring `enum retro_key`.
+///
+/// Keys that produce a character are named after the unshifted character on a US layout.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RetroKey {
+  #[default]
+  Unknown = 0,
+  Backspace = 8,
+  Tab = 9,
+  Clear = 12,
+  Return = 13,
+  Pause = 19,
+  Escape = 27,
+  Space = 32,
+  Exclaim = 33,
+  DoubleQuote = 34,
+  Hash = 35,
+  Dollar = 36,
+  Ampersand = 38,
+  Quote = 39,
+  LeftParen = 40,
+  RightParen = 41,
+  Asterisk = 42,
+  Plus = 43,
+  Comma = 44,
+  Minus = 45,
+  Period = 46,
+  Slash = 47,
+  Num0 = 48,
+  Num1 = 49,
+  Num2 = 50,
+  Num3 = 51,
+  Num4 = 52,
+  Num5 = 53,
+  Num6 = 54,
+  Num7 = 55,
+  Num8 = 56,
+  Num9 = 57,
+  Colon = 58,
+  Semicolon = 59,
+  Less = 60,
+  Equals = 61,
+  Greater = 62,
+  Question = 63,
+  At = 64,
+  LeftBracket = 91,
+  Backslash = 92,
+  RightBracket = 93,
+  Caret = 94,
+  Underscore = 95,
+  Backquote = 96,
+  A = 97,
+  B = 98,
+  C = 99,
+  D = 100,
+  E = 101,
+  F = 102,
+  G = 103,
+  H = 104,
+  I = 105,
+  J = 106,
+  K = 107,
+  L = 108,
+  M = 109,
+  N = 110,
+  O = 111,
+  P = 112,
+  Q = 113,
+  R = 114,
+  S = 115,
+  T = 116,
+  U = 117,
+  V = 118,
+  W = 119,
+  X = 120,
+  Y = 121,
+  Z = 122,
+  LeftBrace = 123,
+  Bar = 124,
+  RightBrace = 125,
+  Tilde = 126,
+  Delete = 127,
+  Keypad0 = 256,
+  Keypad1 = 257,
+  Keypad2 = 258,
+  Keypad3 = 259,
+  Keypad4 = 260,
+  Keypad5 = 261,
+  Keypad6 = 262,
+  Keypad7 = 263,
+  Keypad8 = 264,
+  Keypad9 = 265,
+  KeypadPeriod = 266,
+  KeypadDivide = 267,
+  KeypadMultiply = 268,
+  KeypadMinus = 269,
+  KeypadPlus = 270,
+  KeypadEnter = 271,
+  KeypadEquals = 272,
+  Up = 273,
+  Down = 274,
+  Right = 275,
+  Left = 276,
+  Insert = 277,
+  Home = 278,
+  End = 279,
+  PageUp = 280,
+  PageDown = 281,
+  F1 = 282,
+  F2 = 283,
+  F3 = 284,
+  F4 = 285,
+  F5 = 286,
+  F6 = 287,
+  F7 = 288,
+  F8 = 289,
+  F9 = 290,
+  F10 = 291,
+  F11 = 292,
+  F12 = 293,
+  F13 = 294,
+  F14 = 295,
+  F15 = 296,
+  NumLock = 300,
+  CapsLock = 301,
+  ScrollLock = 302,
+  RightShift = 303,
+  LeftShift = 304,
+  RightCtrl = 305,
+  LeftCtrl = 306,
+  RightAlt = 307,
+  LeftAlt = 308,
+  RightMeta = 309,
+  LeftMeta = 310,
+  LeftSuper = 311,
+  RightSuper = 312,
+  Mode = 313,
+  Compose = 314,
+  Help = 315,
+  Print = 316,
+  SysReq = 317,
+  Break = 318,
+  Menu = 319,
+  Power = 320,
+  Euro = 321,
+  Undo = 322,
+  Oem102 = 323,
+}
+
+impl RetroKey {
+  /// Every key, in ascending order of key code.
+  pub(crate) const ALL: &'static [RetroKey] = &[
+    Self::Unknown,
+    Self::Backspace,
+    Self::Tab,
+    Self::Clear,
+    Self::Return,
+    Self::Pause,
+    Self::Escape,
+    Self::Space,
+    Self::Exclaim,
+    Self::DoubleQuote,
+    Self::Hash,
+    Self::Dollar,
+    Self::Ampersand,
+    Self::Quote,
+    Self::LeftParen,
+    Self::RightParen,
+    Self::Asterisk,
+    Self::Plus,
+    Self::Comma,
+    Self::Minus,
+    Self::Period,
+    Self::Slash,
+    Self::Num0,
+    Self::Num1,
+    Self::Num2,
+    Self::Num3,
+    Self::Num4,
+    Self::Num5,
+    Self::Num6,
+    Self::Num7,
+    Self::Num8,
+    Self::Num9,
+    Self::Colon,
+    Self::Semicolon,
+    Self::Less,
+    Self::Equals,
+    Self::Greater,
+    Self::Question,
+    Self::At,
+    Self::LeftBracket,
+    Self::Backslash,
+    Self::RightBracket,
+    Self::Caret,
+    Self::Underscore,
+    Self::Backquote,
+    Self::A,
+    Self::B,
+    Self::C,
+    Self::D,
+    Self::E,
+    Self::F,
+    Self::G,
+    Self::H,
+    Self::I,
+    Self::J,
+    Self::K,
+    Self::L,
+    Self::M,
+    Self::N,
+    Self::O,
+    Self::P,
+    Self::Q,
+    Self::R,
+    Self::S,
+    Self::T,
+    Self::U,
+    Self::V,
+    Self::W,
+    Self::X,
+    Self::Y,
+    Self::Z,
+    Self::LeftBrace,
+    Self::Bar,
+    Self::RightBrace,
+    Self::Tilde,
+    Self::Delete,
+    Self::Keypad0,
+    Self::Keypad1,
+    Self::Keypad2,
+    Self::Keypad3,
+    Self::Keypad4,
+    Self::Keypad5,
+    Self::Keypad6,
+    Self::Keypad7,
+    Self::Keypad8,
+    Self::Keypad9,
+    Self::KeypadPeriod,
+    Self::KeypadDivide,
+    Self::KeypadMultiply,
+    Self::KeypadMinus,
+    Self::KeypadPlus,
+    Self::KeypadEnter,
+    Self::KeypadEquals,
+    Self::Up,
+    Self::Down,
+    Self::Right,
+    Self::Left,
+    Self::Insert,
+    Self::Home,
+    Self::End,
+    Self::PageUp,
+    Self::PageDown,
+    Self::F1,
+    Self::F2,
+    Self::F3,
+    Self::F4,
+    Self::F5,
+    Self::F6,
+    Self::F7,
+    Self::F8,
+    Self::F9,
+    Self::F10,
+    Self::F11,
+    Self::F12,
+    Self::F13,
+    Self::F14,
+    Self::F15,
+    Self::NumLock,
+    Self::CapsLock,
+    Self::ScrollLock,
+    Self::RightShift,
+    Self::LeftShift,
+    Self::RightCtrl,
+    Self::LeftCtrl,
+    Self::RightAlt,
+    Self::LeftAlt,
+    Self::RightMeta,
+    Self::LeftMeta,
+    Self::LeftSuper,
+    Self::RightSuper,
+    Self::Mode,
+    Self::Compose,
+    Self::Help,
+    Self::Print,
+    Self::SysReq,
+    Self::Break,
+    Self::Menu,
+    Self::Power,
+    Self::Euro,
+    Self::Undo,
+    Self::Oem102,
+  ];
+}
+
+impl From<RetroKey> for c_uint {
+  fn from(key: RetroKey) -> Self {
+    key as c_uint
+  }
+}
+
+impl TryFrom<c_uint> for RetroKey {
+  type Error = TryFromRetroKeyError;
+
+  fn try_from(n: c_uint) -> Result<Self, Self::Error> {
+    Ok(match n {
+      0 => Self::Unknown,
+      8 => Self::Backspace,
+      9 => Self::Tab,
+      12 => Self::Clear,
+      13 => Self::Return,
+      19 => Self::Pause,
+      27 => Self::Escape,
+      32 => Self::Space,
+      33 => Self::Exclaim,
+      34 => Self::DoubleQuote,
+      35 => Self::Hash,
+      36 => Self::Dollar,
+      38 => Self::Ampersand,
+      39 => Self::Quote,
+      40 => Self::LeftParen,
+      41 => Self::RightParen,
+      42 => Self::Asterisk,
+      43 => Self::Plus,
+      44 => Self::Comma,
+      45 => Self::Minus,
+      46 => Self::Period,
+      47 => Self::Slash,
+      48 => Self::Num0,
+      49 => Self::Num1,
+      50 => Self::Num2,
+      51 => Self::Num3,
+      52 => Self::Num4,
+      53 => Self::Num5,
+      54 => Self::Num6,
+      55 => Self::Num7,
+      56 => Self::Num8,
+      57 => Self::Num9,
+      58 => Self::Colon,
+      59 => Self::Semicolon,
+      60 => Self::Less,
+      61 => Self::Equals,
+      62 => Self::Greater,
+      63 => Self::Question,
+      64 => Self::At,
+      91 => Self::LeftBracket,
+      92 => Self::Backslash,
+      93 => Self::RightBracket,
+      94 => Self::Caret,
+      95 => Self::Underscore,
+      96 => Self::Backquote,
+      97 => Self::A,
+      98 => Self::B,
+      99 => Self::C,
+      100 => Self::D,
+      101 => Self::E,
+      102 => Self::F,
+      103 => Self::G,
+      104 => Self::H,
+      105 => Self::I,
+      106 => Self::J,
+      107 => Self::K,
+      108 => Self::L,
+      109 => Self::M,
+      110 => Self::N,
+      111 => Self::O,
+      112 => Self::P,
+      113 => Self::Q,
+      114 => Self::R,
+      115 => Self::S,
+      116 => Self::T,
+      117 => Self::U,
+      118 => Self::V,
+      119 => Self::W,
+      120 => Self::X,
+      121 => Self::Y,
+      122 => Self::Z,
+      123 => Self::LeftBrace,
+      124 => Self::Bar,
+      125 => Self::RightBrace,
+      126 => Self::Tilde,
+      127 => Self::Delete,
+      256 => Self::Keypad0,
+      257 => Self::Keypad1,
+      258 => Self::Keypad2,
+      259 => Self::Keypad3,
+      260 => Self::Keypad4,
+      261 => Self::Keypad5,
+      262 => Self::Keypad6,
+      263 => Self::Keypad7,
+      264 => Self::Keypad8,
+      265 => Self::Keypad9,
+      266 => Self::KeypadPeriod,
+      267 => Self::KeypadDivide,
+      268 => Self::KeypadMultiply,
+      269 => Self::KeypadMinus,
+      270 => Self::KeypadPlus,
+      271 => Self::KeypadEnter,
+      272 => Self::KeypadEquals,
+      273 => Self::Up,
+      274 => Self::Down,
+      275 => Self::Right,
+      276 => Self::Left,
+      277 => Self::Insert,
+      278 => Self::Home,
+      279 => Self::End,
+      280 => Self::PageUp,
+      281 => Self::PageDown,
+      282 => Self::F1,
+      283 => Self::F2,
+      284 => Self::F3,
+      285 => Self::F4,
+      286 => Self::F5,
+      287 => Self::F6,
+      288 => Self::F7,
+      289 => Self::F8,
+      290 => Self::F9,
+      291 => Self::F10,
+      292 => Self::F11,
+      293 => Self::F12,
+      294 => Self::F13,
+      295 => Self::F14,
+      296 => Self::F15,
+      300 => Self::NumLock,
+      301 => Self::CapsLock,
+      302 => Self::ScrollLock,
+      303 => Self::RightShift,
+      304 => Self::LeftShift,
+      305 => Self::RightCtrl,
+      306 => Self::LeftCtrl,
+      307 => Self::RightAlt,
+      308 => Self::LeftAlt,
+      309 => Self::RightMeta,
+      310 => Self::LeftMeta,
+      311 => Self::LeftSuper,
+      312 => Self::RightSuper,
+      313 => Self::Mode,
+      314 => Self::Compose,
+      315 => Self::Help,
+      316 => Self::Print,
+      317 => Self::SysReq,
+      318 => Self::Break,
+      319 => Self::Menu,
+      320 => Self::Power,
+      321 => Self::Euro,
+      322 => Self::Undo,
+      323 => Self::Oem102,
+      _ => return Err(TryFromRetroKeyError(())),
+    })
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+// Based on std::num::TryFromIntError.
+// The crate-private field prevents use of the constructor outside the crate.
+pub struct TryFromRetroKeyError(pub(crate) ());
+
+impl Display for TryFromRetroKeyError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "attempted to convert an unknown key code")
+  }
+}
+
+impl Error for TryFromRetroKeyError {}
+
+impl From<Infallible> for TryFromRetroKeyError {
+  fn from(x: Infallible) -> Self {
+    match x {}
+  }
+}
+
+/// The keys held down on the keyboard, as a bit per key code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyboardState([u64; 6]);
+
+impl KeyboardState {
+  /// Returns true if `key` is pressed.
+  pub fn is_pressed(&self, key: RetroKey) -> bool {
+    let (word, bit) = Self::position(key);
+    self.0[word] & (1 << bit) != 0
+  }
+
+  /// Returns a copy with `key` set to `pressed`.
+  pub fn with_key(mut self, key: RetroKey, pressed: bool) -> Self {
+    let (word, bit) = Self::position(key);
+    self.0[word] = self.0[word] & !(1 << bit) | (u64::from(pressed) << bit);
+    self
+  }
+
+  /// Returns the keys that are pressed, in ascending order of key code.
+  pub fn pressed_keys(&self) -> impl Iterator<Item = RetroKey> + '_ {
+    RetroKey::ALL
+      .iter()
+      .copied()
+      .filter(|&key| self.is_pressed(key))
+  }
+
+  fn position(key: RetroKey) -> (usize, u32) {
+    let code = key as usize;
+    (code / 64, (code % 64) as u32)
+  }
+}
+
+/// The modifier keys and lock states that accompany a keyboard event, mirroring `enum retro_mod`.
+#[bitfield(u16, default: 0)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct KeyModifiers {
+  #[bit(0, rw)]
+  shift: bool,
+  #[bit(1, rw)]
+  ctrl: bool,
+  #[bit(2, rw)]
+  alt: bool,
+  #[bit(3, rw)]
+  meta: bool,
+  #[bit(4, rw)]
+  num_lock: bool,
+  #[bit(5, rw)]
+  caps_lock: bool,
+  #[bit(6, rw)]
+  scroll_lock: bool,
+}
+
+/// A key press or release reported through `RETRO_ENVIRONMENT_SET_KEYBOARD_CALLBACK`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyboardEvent {
+  /// True if the key was pressed, false if it was released.
+  pub down: bool,
+  /// The key, or [`RetroKey::Unknown`] for key codes this crate doesn't know about.
+  pub key: RetroKey,
+  /// The text the key produced, if any. Frontends may report the text of a single key press
+  /// as several events, some with [`RetroKey::Unknown`].
+  pub character: Option<char>,
+  pub modifiers: KeyModifiers,
+}
+
+impl KeyboardEvent {
+  pub fn new(down: bool, keycode: c_uint, character: u32, key_modifiers: u16) -> Self {
+    Self {
+      down,
+      key: RetroKey::try_from(keycode).unwrap_or_default(),
+      character: char::from_u32(character).filter(|&c| c != '\0'),
+      modifiers: KeyModifiers::new_with_raw_value(key_modifiers),
+    }
+  }
+}
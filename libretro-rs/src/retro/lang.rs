@@ -0,0 +1,113 @@
+use ::core::convert::Infallible;
+use ::core::fmt::{Display, Formatter};
+use std::error::Error;
+use std::ffi::c_uint;
+
+/// The languages a frontend can report through `RETRO_ENVIRONMENT_GET_LANGUAGE`.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Language {
+  #[default]
+  English = 0,
+  Japanese = 1,
+  French = 2,
+  Spanish = 3,
+  German = 4,
+  Italian = 5,
+  Dutch = 6,
+  PortugueseBrazil = 7,
+  PortuguesePortugal = 8,
+  Russian = 9,
+  Korean = 10,
+  ChineseTraditional = 11,
+  ChineseSimplified = 12,
+  Esperanto = 13,
+  Polish = 14,
+  Vietnamese = 15,
+  Arabic = 16,
+  Greek = 17,
+  Turkish = 18,
+  Slovak = 19,
+  Persian = 20,
+  Hebrew = 21,
+  Asturian = 22,
+  Finnish = 23,
+  Indonesian = 24,
+  Swedish = 25,
+  Ukrainian = 26,
+  Czech = 27,
+  CatalanValencia = 28,
+  Catalan = 29,
+}
+
+impl Language {
+  /// Every language, indexed by its `RETRO_LANGUAGE_*` value.
+  const ALL: [Language; 30] = [
+    Self::English,
+    Self::Japanese,
+    Self::French,
+    Self::Spanish,
+    Self::German,
+    Self::Italian,
+    Self::Dutch,
+    Self::PortugueseBrazil,
+    Self::PortuguesePortugal,
+    Self::Russian,
+    Self::Korean,
+    Self::ChineseTraditional,
+    Self::ChineseSimplified,
+    Self::Esperanto,
+    Self::Polish,
+    Self::Vietnamese,
+    Self::Arabic,
+    Self::Greek,
+    Self::Turkish,
+    Self::Slovak,
+    Self::Persian,
+    Self::Hebrew,
+    Self::Asturian,
+    Self::Finnish,
+    Self::Indonesian,
+    Self::Swedish,
+    Self::Ukrainian,
+    Self::Czech,
+    Self::CatalanValencia,
+    Self::Catalan,
+  ];
+}
+
+impl From<Language> for c_uint {
+  fn from(language: Language) -> Self {
+    language as c_uint
+  }
+}
+
+impl TryFrom<c_uint> for Language {
+  type Error = TryFromLanguageError;
+
+  fn try_from(n: c_uint) -> Result<Self, Self::Error> {
+    Self::ALL
+      .get(n as usize)
+      .copied()
+      .ok_or(TryFromLanguageError(()))
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+// Based on std::num::TryFromIntError.
+// The crate-private field prevents use of the constructor outside the crate.
+pub struct TryFromLanguageError(pub(crate) ());
+
+impl Display for TryFromLanguageError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "attempted to convert an unknown language")
+  }
+}
+
+impl Error for TryFromLanguageError {}
+
+impl From<Infallible> for TryFromLanguageError {
+  fn from(x: Infallible) -> Self {
+    match x {}
+  }
+}
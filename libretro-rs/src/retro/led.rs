@@ -0,0 +1,68 @@
+use crate::ffi::*;
+
+/// Drive and power lights, provided by [`env::Environment::get_led_interface`].
+///
+/// [`env::Environment::get_led_interface`]: crate::retro::env::Environment::get_led_interface
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug)]
+pub struct Led(non_null_retro_set_led_state_t);
+
+impl Led {
+  pub fn new(set_led_state: non_null_retro_set_led_state_t) -> Self {
+    Self(set_led_state)
+  }
+
+  /// Turns the LED with the given index on or off.
+  pub fn set_led_state(&self, index: c_int, on: bool) {
+    unsafe { self.0(index, c_int::from(on)) }
+  }
+}
+
+/// An [`Led`] that only calls the frontend when an LED's state changes, so cores can set
+/// their LEDs every frame without flooding the frontend.
+#[derive(Clone, Debug)]
+pub struct LedStates {
+  led: Led,
+  states: Vec<Option<bool>>,
+}
+
+impl LedStates {
+  pub fn new(led: Led) -> Self {
+    Self {
+      led,
+      states: Vec::new(),
+    }
+  }
+
+  /// Turns the LED with the given index on or off, unless it's already in that state.
+  /// Returns true if the frontend was called.
+  pub fn set_led_state(&mut self, index: c_int, on: bool) -> bool {
+    let slot = match usize::try_from(index) {
+      Ok(slot) => slot,
+      Err(_) => {
+        self.led.set_led_state(index, on);
+        return true;
+      }
+    };
+    if slot >= self.states.len() {
+      self.states.resize(slot + 1, None);
+    }
+    if self.states[slot] == Some(on) {
+      return false;
+    }
+    self.states[slot] = Some(on);
+    self.led.set_led_state(index, on);
+    true
+  }
+
+  /// Returns the state the LED with the given index was last set to.
+  pub fn led_state(&self, index: c_int) -> Option<bool> {
+    let slot = usize::try_from(index).ok()?;
+    self.states.get(slot).copied().flatten()
+  }
+
+  /// Forgets the states of the LEDs, so the next call for each LED reaches the frontend.
+  pub fn clear(&mut self) {
+    self.states.clear();
+  }
+}
@@ -0,0 +1,76 @@
+use crate::ffi::*;
+use std::time::Duration;
+
+/// Location services, provided by [`env::LoadGame::get_location_interface`].
+///
+/// [`env::LoadGame::get_location_interface`]: crate::retro::env::LoadGame::get_location_interface
+#[derive(Clone, Copy, Debug)]
+pub struct Location {
+  start: non_null_retro_location_start_t,
+  stop: non_null_retro_location_stop_t,
+  get_position: non_null_retro_location_get_position_t,
+  set_interval: non_null_retro_location_set_interval_t,
+}
+
+impl Location {
+  pub fn new(
+    start: non_null_retro_location_start_t,
+    stop: non_null_retro_location_stop_t,
+    get_position: non_null_retro_location_get_position_t,
+    set_interval: non_null_retro_location_set_interval_t,
+  ) -> Self {
+    Self {
+      start,
+      stop,
+      get_position,
+      set_interval,
+    }
+  }
+
+  /// Starts listening for changes to the device's location. May be called during
+  /// [`LocationCore::location_initialized`]. Returns true if location services started.
+  ///
+  /// [`LocationCore::location_initialized`]: crate::retro::LocationCore::location_initialized
+  pub fn start(&self) -> bool {
+    unsafe { (self.start)() }
+  }
+
+  /// Stops listening for changes to the device's location.
+  pub fn stop(&self) {
+    unsafe { (self.stop)() }
+  }
+
+  /// Sets how often the location is updated, by time and by distance in meters.
+  /// Frontends may use either, so both should be given.
+  pub fn set_interval(&self, interval: Duration, distance: c_uint) {
+    let interval = interval.as_millis().try_into().unwrap_or(c_uint::MAX);
+    unsafe { (self.set_interval)(interval, distance) }
+  }
+
+  /// Returns the current position, or [`None`] if the frontend failed to get it.
+  pub fn position(&self) -> Option<Position> {
+    let mut position = Position::default();
+    let Position {
+      latitude,
+      longitude,
+      horizontal_accuracy,
+      vertical_accuracy,
+    } = &mut position;
+    let updated =
+      unsafe { (self.get_position)(latitude, longitude, horizontal_accuracy, vertical_accuracy) };
+    updated.then_some(position)
+  }
+}
+
+/// A position reported by [`Location::position`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+  /// Latitude, in degrees.
+  pub latitude: f64,
+  /// Longitude, in degrees.
+  pub longitude: f64,
+  /// Horizontal accuracy, in meters.
+  pub horizontal_accuracy: f64,
+  /// Vertical accuracy, in meters.
+  pub vertical_accuracy: f64,
+}
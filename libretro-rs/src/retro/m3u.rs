@@ -0,0 +1,191 @@
+use crate::ffi::*;
+use crate::retro::{GamePath, Vfs};
+use std::ffi::CString;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR_STR};
+
+/// The disk images listed in an `.m3u` playlist, in order, ready to be served by a
+/// [`DiskControlCore`].
+///
+/// Each line of the playlist is the path of an image, relative to the playlist's directory
+/// unless it's absolute. Relative paths may use `\` as the separator, as playlists written on
+/// Windows do. Lines starting with `#` are comments, except for these extensions:
+/// - `#LABEL:<label>` labels the image on the next line.
+/// - `#SAVEDISK:<label>` adds a blank disk for the game to save to, which the core creates.
+///   The label is optional.
+///
+/// [`DiskControlCore`]: crate::retro::DiskControlCore
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct M3uPlaylist {
+  entries: Vec<M3uEntry>,
+}
+
+impl M3uPlaylist {
+  /// Reads the playlist at the path of `game` through `vfs`.
+  pub fn load(vfs: &Vfs, game: &GamePath) -> io::Result<Self> {
+    let path = Path::new(game.path().as_str());
+    let mut text = String::new();
+    vfs.open(path)?.read_to_string(&mut text)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok(Self::parse(&text, dir))
+  }
+
+  /// Parses the text of a playlist, resolving relative paths against `dir`.
+  pub fn parse(text: &str, dir: &Path) -> Self {
+    let mut entries = Vec::new();
+    let mut label = None;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+      let line = line.trim();
+      if let Some(next_label) = line.strip_prefix("#LABEL:") {
+        label = c_string(next_label.trim());
+      } else if let Some(save_label) = line.strip_prefix("#SAVEDISK:") {
+        let save_label = c_string(save_label.trim());
+        entries.push(M3uEntry::SaveDisk {
+          label: save_label.or_else(|| label.take()),
+        });
+        label = None;
+      } else if !line.is_empty() && !line.starts_with('#') {
+        let path = dir.join(entry_path(line));
+        let label = label
+          .take()
+          .or_else(|| path.file_stem().and_then(|stem| c_string(stem.to_str()?)));
+        if let Some(path) = path.to_str().and_then(c_string) {
+          entries.push(M3uEntry::Image { path, label });
+        }
+      }
+    }
+    Self { entries }
+  }
+
+  pub fn entries(&self) -> &[M3uEntry] {
+    &self.entries
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// The path of the image at `index`, for [`DiskControlCore::get_image_path`].
+  ///
+  /// [`DiskControlCore::get_image_path`]: crate::retro::DiskControlCore::get_image_path
+  pub fn image_path(&self, index: c_uint) -> Option<&CStr> {
+    self.entries.get(index as usize)?.path()
+  }
+
+  /// The label of the image at `index`, for [`DiskControlCore::get_image_label`].
+  ///
+  /// [`DiskControlCore::get_image_label`]: crate::retro::DiskControlCore::get_image_label
+  pub fn image_label(&self, index: c_uint) -> Option<&CStr> {
+    self.entries.get(index as usize)?.label()
+  }
+}
+
+/// An entry of an [`M3uPlaylist`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum M3uEntry {
+  /// A disk image. Unless the playlist labels it, the label is the file name without its
+  /// extension.
+  Image {
+    path: CString,
+    label: Option<CString>,
+  },
+  /// A blank disk for the game to save to, added by `#SAVEDISK:`.
+  SaveDisk { label: Option<CString> },
+}
+
+impl M3uEntry {
+  pub fn path(&self) -> Option<&CStr> {
+    match self {
+      Self::Image { path, .. } => Some(path),
+      Self::SaveDisk { .. } => None,
+    }
+  }
+
+  pub fn label(&self) -> Option<&CStr> {
+    match self {
+      Self::Image { label, .. } | Self::SaveDisk { label } => label.as_deref(),
+    }
+  }
+}
+
+/// Converts the separators of a relative entry to the platform's. Paths starting with a drive
+/// letter are absolute on Windows and left alone.
+fn entry_path(line: &str) -> PathBuf {
+  match line.as_bytes() {
+    [drive, b':', ..] if drive.is_ascii_alphabetic() => PathBuf::from(line),
+    _ => PathBuf::from(line.replace('\\', MAIN_SEPARATOR_STR)),
+  }
+}
+
+/// Returns [`None`] for empty strings and strings containing nul bytes.
+fn c_string(str: &str) -> Option<CString> {
+  match str {
+    "" => None,
+    str => CString::new(str).ok(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn image(path: &str, label: &str) -> M3uEntry {
+    M3uEntry::Image {
+      path: CString::new(path).unwrap(),
+      label: Some(CString::new(label).unwrap()),
+    }
+  }
+
+  #[test]
+  fn resolves_relative_paths_and_labels() {
+    let text = "\u{feff}#EXTM3U\r\nDisc 1.cue\r\n\r\n#LABEL: Second disc \r\nsub/Disc 2.cue\r\n/abs/Disc 3.chd\r\n";
+    let playlist = M3uPlaylist::parse(text, Path::new("/games"));
+    assert_eq!(
+      playlist.entries(),
+      [
+        image("/games/Disc 1.cue", "Disc 1"),
+        image("/games/sub/Disc 2.cue", "Second disc"),
+        image("/abs/Disc 3.chd", "Disc 3"),
+      ]
+    );
+    assert_eq!(playlist.image_label(1), Some(c"Second disc"));
+    assert_eq!(playlist.image_path(3), None);
+  }
+
+  #[test]
+  fn save_disks() {
+    let text = "a.adf\n#SAVEDISK:\n#LABEL:Saves\n#SAVEDISK:\n#SAVEDISK:Scores\n";
+    let playlist = M3uPlaylist::parse(text, Path::new(""));
+    assert_eq!(
+      playlist.entries(),
+      [
+        image("a.adf", "a"),
+        M3uEntry::SaveDisk { label: None },
+        M3uEntry::SaveDisk {
+          label: Some(CString::new("Saves").unwrap())
+        },
+        M3uEntry::SaveDisk {
+          label: Some(CString::new("Scores").unwrap())
+        },
+      ]
+    );
+    assert_eq!(playlist.image_path(1), None);
+  }
+
+  #[test]
+  fn windows_separators() {
+    let text = "Disc 1\\Disc 1.cue\r\n#LABEL:Second disc\r\nsub\\dir\\Disc 2.cue\r\n";
+    let playlist = M3uPlaylist::parse(text, Path::new("/games"));
+    assert_eq!(
+      playlist.entries(),
+      [
+        image("/games/Disc 1/Disc 1.cue", "Disc 1"),
+        image("/games/sub/dir/Disc 2.cue", "Second disc"),
+      ]
+    );
+  }
+}
@@ -0,0 +1,274 @@
+use crate::ffi::*;
+
+/// Raw MIDI input and output, provided by [`env::Environment::get_midi_interface`].
+///
+/// [`env::Environment::get_midi_interface`]: crate::retro::env::Environment::get_midi_interface
+#[derive(Clone, Copy, Debug)]
+pub struct Midi {
+  input_enabled: non_null_retro_midi_input_enabled_t,
+  output_enabled: non_null_retro_midi_output_enabled_t,
+  read: non_null_retro_midi_read_t,
+  write: non_null_retro_midi_write_t,
+  flush: non_null_retro_midi_flush_t,
+}
+
+impl Midi {
+  pub fn new(
+    input_enabled: non_null_retro_midi_input_enabled_t,
+    output_enabled: non_null_retro_midi_output_enabled_t,
+    read: non_null_retro_midi_read_t,
+    write: non_null_retro_midi_write_t,
+    flush: non_null_retro_midi_flush_t,
+  ) -> Self {
+    Self {
+      input_enabled,
+      output_enabled,
+      read,
+      write,
+      flush,
+    }
+  }
+
+  /// Returns true if the user has enabled MIDI input.
+  pub fn input_enabled(&self) -> bool {
+    unsafe { (self.input_enabled)() }
+  }
+
+  /// Returns true if the user has enabled MIDI output.
+  pub fn output_enabled(&self) -> bool {
+    unsafe { (self.output_enabled)() }
+  }
+
+  /// Reads the next byte of the input stream, if there is one.
+  pub fn read(&self) -> Option<u8> {
+    let mut byte = 0;
+    unsafe { (self.read)(&mut byte) }.then_some(byte)
+  }
+
+  /// Writes a byte to the output stream. `delta_time` is the time since the previous write,
+  /// in microseconds. Returns true if the byte was written.
+  pub fn write(&self, byte: u8, delta_time: u32) -> bool {
+    unsafe { (self.write)(byte, delta_time) }
+  }
+
+  /// Sends the bytes written so far. Returns true if successful.
+  pub fn flush(&self) -> bool {
+    unsafe { (self.flush)() }
+  }
+}
+
+/// A MIDI message, as framed by [`MidiParser`]. Channels are numbered from 0.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MidiMessage {
+  NoteOff {
+    channel: u8,
+    key: u8,
+    velocity: u8,
+  },
+  /// Many devices send a note on with a velocity of 0 instead of a note off.
+  NoteOn {
+    channel: u8,
+    key: u8,
+    velocity: u8,
+  },
+  PolyphonicPressure {
+    channel: u8,
+    key: u8,
+    pressure: u8,
+  },
+  ControlChange {
+    channel: u8,
+    controller: u8,
+    value: u8,
+  },
+  ProgramChange {
+    channel: u8,
+    program: u8,
+  },
+  ChannelPressure {
+    channel: u8,
+    pressure: u8,
+  },
+  /// A 14-bit value, centered on `0x2000`.
+  PitchBend {
+    channel: u8,
+    value: u16,
+  },
+  /// A system exclusive message, without the leading `0xF0` and trailing `0xF7`.
+  SysEx(Vec<u8>),
+  /// A system common or system real-time message.
+  System {
+    status: u8,
+    data: Vec<u8>,
+  },
+}
+
+/// Frames a raw MIDI byte stream, such as the bytes from [`Midi::read`], into [`MidiMessage`]s.
+///
+/// Running status is supported, and real-time messages may appear anywhere in the stream,
+/// including in the middle of other messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MidiParser {
+  status: Option<u8>,
+  data: Vec<u8>,
+  sysex: Option<Vec<u8>>,
+}
+
+impl MidiParser {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Feeds a byte to the parser, returning the message it completes, if any.
+  pub fn push(&mut self, byte: u8) -> Option<MidiMessage> {
+    match byte {
+      0xF8..=0xFF => Some(MidiMessage::System {
+        status: byte,
+        data: Vec::new(),
+      }),
+      0xF7 => {
+        self.status = None;
+        self.sysex.take().map(MidiMessage::SysEx)
+      }
+      0xF0 => {
+        self.status = None;
+        self.sysex = Some(Vec::new());
+        None
+      }
+      0x80..=0xF6 => {
+        // Any other status byte ends an unfinished system exclusive message.
+        self.sysex = None;
+        self.status = Some(byte);
+        self.data.clear();
+        self.complete()
+      }
+      _ => {
+        if let Some(sysex) = &mut self.sysex {
+          sysex.push(byte);
+          return None;
+        }
+        // Data without a status byte can't be interpreted.
+        self.status?;
+        self.data.push(byte);
+        self.complete()
+      }
+    }
+  }
+
+  /// Returns the pending message if it has all of its data bytes.
+  fn complete(&mut self) -> Option<MidiMessage> {
+    let status = self.status?;
+    if self.data.len() < data_len(status) {
+      return None;
+    }
+    let data = std::mem::take(&mut self.data);
+    if status >= 0xF0 {
+      // System common messages don't take part in running status.
+      self.status = None;
+      return Some(MidiMessage::System { status, data });
+    }
+    let channel = status & 0x0F;
+    Some(match status & 0xF0 {
+      0x80 => MidiMessage::NoteOff {
+        channel,
+        key: data[0],
+        velocity: data[1],
+      },
+      0x90 => MidiMessage::NoteOn {
+        channel,
+        key: data[0],
+        velocity: data[1],
+      },
+      0xA0 => MidiMessage::PolyphonicPressure {
+        channel,
+        key: data[0],
+        pressure: data[1],
+      },
+      0xB0 => MidiMessage::ControlChange {
+        channel,
+        controller: data[0],
+        value: data[1],
+      },
+      0xC0 => MidiMessage::ProgramChange {
+        channel,
+        program: data[0],
+      },
+      0xD0 => MidiMessage::ChannelPressure {
+        channel,
+        pressure: data[0],
+      },
+      _ => MidiMessage::PitchBend {
+        channel,
+        value: u16::from(data[0]) | u16::from(data[1]) << 7,
+      },
+    })
+  }
+}
+
+/// The number of data bytes that follow a status byte.
+fn data_len(status: u8) -> usize {
+  match status {
+    0xC0..=0xDF | 0xF1 | 0xF3 => 1,
+    0x80..=0xEF | 0xF2 => 2,
+    _ => 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(bytes: &[u8]) -> Vec<MidiMessage> {
+    let mut parser = MidiParser::new();
+    bytes.iter().filter_map(|&byte| parser.push(byte)).collect()
+  }
+
+  #[test]
+  fn running_status() {
+    let messages = parse(&[0x91, 60, 100, 62, 0, 0xB0, 7, 127]);
+    assert_eq!(
+      messages,
+      [
+        MidiMessage::NoteOn {
+          channel: 1,
+          key: 60,
+          velocity: 100
+        },
+        MidiMessage::NoteOn {
+          channel: 1,
+          key: 62,
+          velocity: 0
+        },
+        MidiMessage::ControlChange {
+          channel: 0,
+          controller: 7,
+          value: 127
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn sysex_with_interleaved_real_time() {
+    let messages = parse(&[0xF0, 0x41, 0xF8, 0x10, 0xF7, 0xE0, 0x00, 0x40]);
+    assert_eq!(
+      messages,
+      [
+        MidiMessage::System {
+          status: 0xF8,
+          data: vec![]
+        },
+        MidiMessage::SysEx(vec![0x41, 0x10]),
+        MidiMessage::PitchBend {
+          channel: 0,
+          value: 0x2000
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn stray_data_is_ignored() {
+    assert_eq!(parse(&[60, 100, 0xF0, 1, 0x90, 60]), []);
+  }
+}
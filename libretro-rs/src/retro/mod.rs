@@ -1,21 +1,45 @@
 pub mod av;
+pub mod camera;
 pub mod cores;
 pub mod device;
 pub mod env;
 pub mod error;
 pub mod fs;
 pub mod game;
+pub mod key;
+pub mod lang;
+pub mod led;
+pub mod location;
 pub mod log;
+pub mod m3u;
 pub mod mem;
+pub mod midi;
+pub mod options;
+pub mod perf;
+pub mod rumble;
+pub mod sensor;
 pub mod str;
+pub mod vfs;
 
 pub use self::av::*;
+pub use self::camera::*;
 pub use self::cores::*;
 pub use self::device::*;
 // env deliberately omitted
 pub use self::error::*;
 pub use self::fs::*;
 pub use self::game::*;
+pub use self::key::*;
+pub use self::lang::*;
+pub use self::led::*;
+pub use self::location::*;
 pub use self::log::*;
+pub use self::m3u::*;
 pub use self::mem::*;
+pub use self::midi::*;
+pub use self::options::*;
+pub use self::perf::*;
+pub use self::rumble::*;
+pub use self::sensor::*;
 pub use self::str::*;
+pub use self::vfs::*;
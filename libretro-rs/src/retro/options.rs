@@ -0,0 +1,838 @@
+//! Typed core options.
+//!
+//! A core declares its options as a type implementing [`CoreOptions`], registers
+//! them with [`SetEnvironment::set_core_options`] and reads their parsed values
+//! back with [`Environment::get_core_options`].
+//!
+//! With the `derive` feature enabled, [`CoreOptions`] can be derived for a struct
+//! and [`CoreOptionValue`] for an enum:
+//!
+//! ```ignore
+//! use libretro_rs::retro::options::*;
+//!
+//! #[derive(Clone, Debug, PartialEq, CoreOptionValue)]
+//! enum Palette {
+//!   #[option(label = "Grayscale")]
+//!   Gray,
+//!   #[option(value = "lcd", label = "LCD Green")]
+//!   Green,
+//! }
+//!
+//! #[derive(CoreOptions)]
+//! #[options(prefix = "foo_", category(key = "video", desc = "Video"))]
+//! struct Options {
+//!   #[option(desc = "Palette", category = "video", default = "gray")]
+//!   palette: Palette,
+//!   #[option(desc = "Frameskip", min = 0, max = 10, default = 0)]
+//!   frameskip: u8,
+//!   #[option(key = "foo_audio", desc = "Audio", default = true)]
+//!   audio_enabled: bool,
+//!   #[option(desc = "Volume", min = 0, max = 100, step = 10, visible_if(audio_enabled = true))]
+//!   volume: u8,
+//! }
+//! ```
+//!
+//! `visible_if` hides an option in the frontend's menu unless the options named
+//! by their fields have the given value, or one of a list of values, e.g.
+//! `visible_if(palette = ["gray", "lcd"])`.
+//!
+//! The derive macro also generates an `OptionsChange` enum for the example above,
+//! which [`Run::get_core_options_update`] uses to report which options changed,
+//! and which [`Environment::set_core_option`] takes to change an option from the core.
+//! For that reason, the type of every option must implement [`Clone`], [`Debug`]
+//! and [`PartialEq`].
+//!
+//! Options are declared in English, which is the fallback for any text that isn't
+//! translated. Translations are attached with [`CoreOptions::TRANSLATIONS`], or the
+//! `translations` attribute of the derive macro:
+//!
+//! ```ignore
+//! const TRANSLATIONS: &[OptionsTranslation] = &[OptionsTranslation::new(
+//!   Language::French,
+//!   &[OptionDefinition::new(c"foo_palette", c"Palette", &[
+//!     OptionValue::new(c"gray").with_label(c"Niveaux de gris"),
+//!     OptionValue::new(c"lcd").with_label(c"LCD vert"),
+//!   ])],
+//! )
+//! .with_categories(&[OptionCategory::new(c"video", c"Vidéo")])];
+//!
+//! #[derive(CoreOptions)]
+//! #[options(prefix = "foo_", translations = TRANSLATIONS)]
+//! struct Options {
+//!   // ...
+//! }
+//! ```
+//!
+//! [`SetEnvironment::set_core_options`]: crate::retro::env::SetEnvironment::set_core_options
+//! [`Environment::get_core_options`]: crate::retro::env::Environment::get_core_options
+//! [`Run::get_core_options_update`]: crate::retro::env::Run::get_core_options_update
+//! [`Environment::set_core_option`]: crate::retro::env::Environment::set_core_option
+
+use crate::ffi::*;
+use crate::retro::lang::Language;
+use core::ffi::*;
+use core::ptr;
+use std::borrow::Cow;
+use std::ffi::CString;
+
+#[cfg(feature = "derive")]
+pub use libretro_rs_derive::{CoreOptionValue, CoreOptions};
+
+/// A set of core options.
+///
+/// Prefer deriving this trait; see [the module's documentation](self).
+pub trait CoreOptions: Sized {
+  /// A change to a single option, carrying its new value.
+  /// The derive macro generates an enum named after the options type,
+  /// e.g. `OptionsChange` for `Options`, with a variant per option.
+  type Change: OptionChange;
+
+  /// The option categories. Empty if the options aren't categorized.
+  const CATEGORIES: &'static [OptionCategory];
+
+  /// The option definitions, in the order the frontend should display them.
+  /// These are in English, which is used for any text missing from [`CoreOptions::TRANSLATIONS`].
+  const DEFINITIONS: &'static [OptionDefinition];
+
+  /// Translations of the categories and definitions into other languages.
+  const TRANSLATIONS: &'static [OptionsTranslation] = &[];
+
+  /// Creates a value from the current value of each option.
+  ///
+  /// `get` is called with the key of each option in [`CoreOptions::DEFINITIONS`].
+  /// Options that are missing or fail to parse take their default value.
+  fn from_values<'a>(get: impl FnMut(&'static CStr) -> Option<&'a CStr>) -> Self;
+
+  /// Replaces `self` with `new`, returning a change for every option whose value differs.
+  fn update(&mut self, new: Self) -> Vec<Self::Change>;
+}
+
+/// A new value for a single core option.
+pub trait OptionChange {
+  /// The definition of the option being changed.
+  fn definition(&self) -> &'static OptionDefinition;
+
+  /// The new value, which should be one of [`OptionDefinition::values`].
+  fn value(&self) -> Cow<'static, CStr>;
+}
+
+/// Types that can be stored in a core option.
+pub trait CoreOptionValue: Sized {
+  /// Every value of this type, if they can be enumerated.
+  ///
+  /// Types that leave this empty, such as integers, need their values
+  /// declared alongside each option.
+  const VALUES: &'static [OptionValue] = &[];
+
+  /// Parses the value reported by the frontend.
+  fn from_value(value: &CStr) -> Option<Self>;
+
+  /// Converts this value into the string that represents it.
+  fn to_value(&self) -> Cow<'static, CStr>;
+}
+
+impl CoreOptionValue for bool {
+  const VALUES: &'static [OptionValue] = &[
+    OptionValue::new(__c_str(b"disabled\0")),
+    OptionValue::new(__c_str(b"enabled\0")),
+  ];
+
+  fn from_value(value: &CStr) -> Option<Self> {
+    match value.to_bytes() {
+      b"enabled" | b"true" => Some(true),
+      b"disabled" | b"false" => Some(false),
+      _ => None,
+    }
+  }
+
+  fn to_value(&self) -> Cow<'static, CStr> {
+    let index = *self as usize;
+    Cow::Borrowed(Self::VALUES[index].value())
+  }
+}
+
+macro_rules! int_option_value {
+  ($($int:ty),*) => {
+    $(
+      impl CoreOptionValue for $int {
+        fn from_value(value: &CStr) -> Option<Self> {
+          value.to_str().ok()?.parse().ok()
+        }
+
+        fn to_value(&self) -> Cow<'static, CStr> {
+          Cow::Owned(CString::new(self.to_string()).expect("integers should not contain nul bytes"))
+        }
+      }
+    )*
+  };
+}
+
+int_option_value!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// One of the values a core option can take.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OptionValue {
+  value: &'static CStr,
+  label: Option<&'static CStr>,
+}
+
+impl OptionValue {
+  /// Creates a value that the frontend displays as-is.
+  pub const fn new(value: &'static CStr) -> Self {
+    Self { value, label: None }
+  }
+
+  /// Sets the label that the frontend displays instead of the value.
+  pub const fn with_label(mut self, label: &'static CStr) -> Self {
+    self.label = Some(label);
+    self
+  }
+
+  pub fn value(&self) -> &'static CStr {
+    self.value
+  }
+
+  pub fn label(&self) -> Option<&'static CStr> {
+    self.label
+  }
+}
+
+impl From<&OptionValue> for retro_core_option_value {
+  fn from(value: &OptionValue) -> Self {
+    Self {
+      value: value.value.as_ptr(),
+      label: as_ptr_or_null(value.label),
+    }
+  }
+}
+
+/// A category that groups core options in the frontend's menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OptionCategory {
+  key: &'static CStr,
+  desc: &'static CStr,
+  info: Option<&'static CStr>,
+}
+
+impl OptionCategory {
+  /// Minimal constructor. `key` may only contain `[a-zA-Z0-9_-]`.
+  pub const fn new(key: &'static CStr, desc: &'static CStr) -> Self {
+    Self {
+      key,
+      desc,
+      info: None,
+    }
+  }
+
+  /// Sets additional information about the category.
+  pub const fn with_info(mut self, info: &'static CStr) -> Self {
+    self.info = Some(info);
+    self
+  }
+
+  pub fn key(&self) -> &'static CStr {
+    self.key
+  }
+
+  pub fn desc(&self) -> &'static CStr {
+    self.desc
+  }
+
+  pub fn info(&self) -> Option<&'static CStr> {
+    self.info
+  }
+}
+
+impl From<&OptionCategory> for retro_core_option_v2_category {
+  fn from(category: &OptionCategory) -> Self {
+    Self {
+      key: category.key.as_ptr(),
+      desc: category.desc.as_ptr(),
+      info: as_ptr_or_null(category.info),
+    }
+  }
+}
+
+/// The declaration of a single core option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OptionDefinition {
+  key: &'static CStr,
+  desc: &'static CStr,
+  desc_categorized: Option<&'static CStr>,
+  info: Option<&'static CStr>,
+  info_categorized: Option<&'static CStr>,
+  category_key: Option<&'static CStr>,
+  values: &'static [OptionValue],
+  default_value: Option<&'static CStr>,
+  visible_if: &'static [OptionCondition],
+}
+
+impl OptionDefinition {
+  /// Minimal constructor. `key` should be prefixed with the core's name to
+  /// avoid collisions, and may only contain `[a-zA-Z0-9_-]`.
+  ///
+  /// At most [`RETRO_NUM_CORE_OPTION_VALUES_MAX`]` - 1` values are sent to the
+  /// frontend. Without [`OptionDefinition::with_default`], the first value is
+  /// the default.
+  pub const fn new(
+    key: &'static CStr,
+    desc: &'static CStr,
+    values: &'static [OptionValue],
+  ) -> Self {
+    Self {
+      key,
+      desc,
+      desc_categorized: None,
+      info: None,
+      info_categorized: None,
+      category_key: None,
+      values,
+      default_value: None,
+      visible_if: &[],
+    }
+  }
+
+  /// Sets the description used when the frontend displays categories.
+  pub const fn with_desc_categorized(mut self, desc: &'static CStr) -> Self {
+    self.desc_categorized = Some(desc);
+    self
+  }
+
+  /// Sets additional information about the option.
+  pub const fn with_info(mut self, info: &'static CStr) -> Self {
+    self.info = Some(info);
+    self
+  }
+
+  /// Sets the information used when the frontend displays categories.
+  pub const fn with_info_categorized(mut self, info: &'static CStr) -> Self {
+    self.info_categorized = Some(info);
+    self
+  }
+
+  /// Places the option in the category with the given key.
+  pub const fn with_category(mut self, category_key: &'static CStr) -> Self {
+    self.category_key = Some(category_key);
+    self
+  }
+
+  /// Sets the default value, which must be one of the option's values.
+  pub const fn with_default(mut self, default_value: &'static CStr) -> Self {
+    self.default_value = Some(default_value);
+    self
+  }
+
+  /// Only shows the option in the frontend's menu while every condition is met.
+  pub const fn with_visible_if(mut self, conditions: &'static [OptionCondition]) -> Self {
+    self.visible_if = conditions;
+    self
+  }
+
+  pub fn key(&self) -> &'static CStr {
+    self.key
+  }
+
+  pub fn desc(&self) -> &'static CStr {
+    self.desc
+  }
+
+  pub fn desc_categorized(&self) -> Option<&'static CStr> {
+    self.desc_categorized
+  }
+
+  pub fn info(&self) -> Option<&'static CStr> {
+    self.info
+  }
+
+  pub fn info_categorized(&self) -> Option<&'static CStr> {
+    self.info_categorized
+  }
+
+  pub fn category_key(&self) -> Option<&'static CStr> {
+    self.category_key
+  }
+
+  pub fn values(&self) -> &'static [OptionValue] {
+    self.values
+  }
+
+  /// The default value, which falls back to the first value if not set.
+  pub fn default_value(&self) -> Option<&'static CStr> {
+    self
+      .default_value
+      .or_else(|| self.values.first().map(OptionValue::value))
+  }
+
+  pub fn visible_if(&self) -> &'static [OptionCondition] {
+    self.visible_if
+  }
+
+  /// Returns true if `value` is one of the option's values.
+  pub fn has_value(&self, value: &CStr) -> bool {
+    self.values.iter().any(|v| v.value == value)
+  }
+
+  /// Parses `value`, or the default value if `value` is missing or invalid.
+  /// If the default value can't be parsed as a `T` either, the first value that can is used.
+  ///
+  /// # Panics
+  /// Panics if none of the option's values can be parsed as a `T`, which indicates that
+  /// the definition doesn't match the type of the option. The derive macro rejects such
+  /// definitions at compile time.
+  pub fn parse<T: CoreOptionValue>(&self, value: Option<&CStr>) -> T {
+    value
+      .and_then(T::from_value)
+      .or_else(|| self.default_value().and_then(T::from_value))
+      .or_else(|| self.values.iter().find_map(|v| T::from_value(v.value)))
+      .unwrap_or_else(|| panic!("core option {:?} has no valid values", self.key))
+  }
+}
+
+impl From<&OptionDefinition> for retro_core_option_v2_definition {
+  fn from(definition: &OptionDefinition) -> Self {
+    const LEN: usize = RETRO_NUM_CORE_OPTION_VALUES_MAX as usize;
+    let mut values = [retro_core_option_value::default(); LEN];
+    // The last element is reserved for the terminator.
+    for (dst, src) in values.iter_mut().zip(definition.values).take(LEN - 1) {
+      *dst = src.into();
+    }
+    Self {
+      key: definition.key.as_ptr(),
+      desc: definition.desc.as_ptr(),
+      desc_categorized: as_ptr_or_null(definition.desc_categorized),
+      info: as_ptr_or_null(definition.info),
+      info_categorized: as_ptr_or_null(definition.info_categorized),
+      category_key: as_ptr_or_null(definition.category_key),
+      values,
+      default_value: as_ptr_or_null(definition.default_value),
+    }
+  }
+}
+
+impl From<&OptionDefinition> for retro_core_option_definition {
+  fn from(definition: &OptionDefinition) -> Self {
+    let v2 = retro_core_option_v2_definition::from(definition);
+    Self {
+      key: v2.key,
+      desc: v2.desc,
+      info: v2.info,
+      values: v2.values,
+      default_value: v2.default_value,
+    }
+  }
+}
+
+/// A condition on the value of another core option, used to decide whether an
+/// option is visible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OptionCondition {
+  key: &'static CStr,
+  values: &'static [&'static CStr],
+}
+
+impl OptionCondition {
+  /// Met while the option with the given key has any of `values`.
+  pub const fn new(key: &'static CStr, values: &'static [&'static CStr]) -> Self {
+    Self { key, values }
+  }
+
+  pub fn key(&self) -> &'static CStr {
+    self.key
+  }
+
+  pub fn values(&self) -> &'static [&'static CStr] {
+    self.values
+  }
+}
+
+/// Evaluates the visibility of every option in `definitions` with conditions.
+///
+/// `get` is called with the key of each option a condition refers to. Options
+/// that are missing or have an invalid value are treated as having their default value.
+pub(crate) fn visibility<'a>(
+  definitions: &'static [OptionDefinition],
+  mut get: impl FnMut(&'static CStr) -> Option<&'a CStr>,
+) -> Vec<(&'static CStr, bool)> {
+  let mut value_of = |key: &'static CStr| {
+    let value = get(key);
+    match definitions.iter().find(|d| d.key == key) {
+      Some(definition) => value
+        .filter(|value| definition.has_value(value))
+        .or_else(|| definition.default_value()),
+      None => value,
+    }
+  };
+  definitions
+    .iter()
+    .filter(|definition| !definition.visible_if.is_empty())
+    .map(|definition| {
+      let visible = definition.visible_if.iter().all(|condition| {
+        value_of(condition.key).is_some_and(|value| condition.values.contains(&value))
+      });
+      (definition.key, visible)
+    })
+    .collect()
+}
+
+/// The strings of a set of core options, translated into another language.
+///
+/// Categories and definitions are matched with the English ones by key, and values
+/// by their value. Only the descriptions, information and labels are used, and any
+/// of them may be left out to fall back to English.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OptionsTranslation {
+  language: Language,
+  categories: &'static [OptionCategory],
+  definitions: &'static [OptionDefinition],
+}
+
+impl OptionsTranslation {
+  /// Minimal constructor.
+  pub const fn new(language: Language, definitions: &'static [OptionDefinition]) -> Self {
+    Self {
+      language,
+      categories: &[],
+      definitions,
+    }
+  }
+
+  /// Sets the translated categories.
+  pub const fn with_categories(mut self, categories: &'static [OptionCategory]) -> Self {
+    self.categories = categories;
+    self
+  }
+
+  pub fn language(&self) -> Language {
+    self.language
+  }
+
+  pub fn categories(&self) -> &'static [OptionCategory] {
+    self.categories
+  }
+
+  pub fn definitions(&self) -> &'static [OptionDefinition] {
+    self.definitions
+  }
+
+  /// Finds the translation for `language` in `translations`. English is never
+  /// translated, since it's the language the options are declared in.
+  pub fn find(translations: &'static [Self], language: Language) -> Option<&'static Self> {
+    if language == Language::English {
+      return None;
+    }
+    translations.iter().find(|t| t.language == language)
+  }
+
+  /// Finds the translated definition with the given key.
+  pub fn definition(&self, key: &CStr) -> Option<&'static OptionDefinition> {
+    self.definitions.iter().find(|d| d.key == key)
+  }
+}
+
+/// Null-terminated option tables in the layout expected by
+/// `RETRO_ENVIRONMENT_SET_CORE_OPTIONS_V2`.
+pub(crate) struct CoreOptionsV2 {
+  categories: Vec<retro_core_option_v2_category>,
+  definitions: Vec<retro_core_option_v2_definition>,
+}
+
+impl CoreOptionsV2 {
+  pub fn new(categories: &[OptionCategory], definitions: &[OptionDefinition]) -> Self {
+    Self {
+      categories: categories
+        .iter()
+        .map(Into::into)
+        .chain(Some(retro_core_option_v2_category::default()))
+        .collect(),
+      definitions: definitions
+        .iter()
+        .map(Into::into)
+        .chain(Some(retro_core_option_v2_definition::default()))
+        .collect(),
+    }
+  }
+
+  /// The returned value borrows from `self`, which must outlive any use of it.
+  pub fn as_raw(&mut self) -> retro_core_options_v2 {
+    retro_core_options_v2 {
+      categories: if self.categories.len() > 1 {
+        self.categories.as_mut_ptr()
+      } else {
+        ptr::null_mut()
+      },
+      definitions: self.definitions.as_mut_ptr(),
+    }
+  }
+}
+
+/// A null-terminated option table in the layout expected by
+/// `RETRO_ENVIRONMENT_SET_CORE_OPTIONS`. Categories are flattened away, so
+/// each option uses its uncategorized description and information.
+pub(crate) struct CoreOptionsV1 {
+  definitions: Vec<retro_core_option_definition>,
+}
+
+impl CoreOptionsV1 {
+  pub fn new(definitions: &[OptionDefinition]) -> Self {
+    Self {
+      definitions: definitions
+        .iter()
+        .map(Into::into)
+        .chain(Some(retro_core_option_definition::default()))
+        .collect(),
+    }
+  }
+
+  /// The first entry of the table, whose address is passed to the frontend.
+  pub fn as_raw(&self) -> &retro_core_option_definition {
+    &self.definitions[0]
+  }
+
+  /// The returned pointer borrows from `self`, which must outlive any use of it.
+  pub fn as_mut_ptr(&mut self) -> *mut retro_core_option_definition {
+    self.definitions.as_mut_ptr()
+  }
+}
+
+/// A null-terminated variable table in the layout expected by
+/// `RETRO_ENVIRONMENT_SET_VARIABLES`.
+///
+/// Each variable is described as `"Description; default|other|values"`, since
+/// the legacy interface has no separate default and no labels. The description
+/// is taken from `translation` when it has one.
+pub(crate) struct Variables {
+  variables: Vec<retro_variable>,
+  // Owns the strings pointed to by `variables`.
+  _values: Vec<CString>,
+}
+
+impl Variables {
+  pub fn new(definitions: &[OptionDefinition], translation: Option<&OptionsTranslation>) -> Self {
+    let values: Vec<CString> = definitions
+      .iter()
+      .map(|definition| {
+        let desc = translation
+          .and_then(|t| t.definition(definition.key))
+          .map_or(definition.desc, OptionDefinition::desc);
+        legacy_value(definition, desc)
+      })
+      .collect();
+    let variables = definitions
+      .iter()
+      .zip(&values)
+      .map(|(definition, value)| retro_variable {
+        key: definition.key.as_ptr(),
+        value: value.as_ptr(),
+      })
+      .chain(Some(retro_variable {
+        key: ptr::null(),
+        value: ptr::null(),
+      }))
+      .collect();
+    Self {
+      variables,
+      _values: values,
+    }
+  }
+
+  /// The first entry of the table, whose address is passed to the frontend.
+  pub fn as_raw(&self) -> &retro_variable {
+    &self.variables[0]
+  }
+}
+
+/// Formats `definition` as a legacy variable value, with the default value first.
+fn legacy_value(definition: &OptionDefinition, desc: &CStr) -> CString {
+  let default = definition.default_value();
+  let mut bytes = desc.to_bytes().to_vec();
+  bytes.extend_from_slice(b"; ");
+  let values = default.into_iter().chain(
+    definition
+      .values
+      .iter()
+      .take(RETRO_NUM_CORE_OPTION_VALUES_MAX as usize - 1)
+      .map(OptionValue::value)
+      .filter(|value| Some(*value) != default),
+  );
+  for (i, value) in values.enumerate() {
+    if i > 0 {
+      bytes.push(b'|');
+    }
+    bytes.extend_from_slice(value.to_bytes());
+  }
+  CString::new(bytes).expect("option strings should not contain nul bytes")
+}
+
+fn as_ptr_or_null(str: Option<&CStr>) -> *const c_char {
+  str.map_or_else(ptr::null, CStr::as_ptr)
+}
+
+/// Creates a C string constant from a byte string with a single trailing nul.
+/// Used by the derive macros.
+#[doc(hidden)]
+pub const fn __c_str(bytes: &'static [u8]) -> &'static CStr {
+  let mut i = 0;
+  while i < bytes.len() {
+    assert!(
+      (bytes[i] == 0) == (i == bytes.len() - 1),
+      "expected a single trailing nul byte"
+    );
+    i += 1;
+  }
+  // Safety: checked above.
+  unsafe { CStr::from_bytes_with_nul_unchecked(bytes) }
+}
+
+/// Returns true if `value` is one of `values`. Used by the derive macros to check
+/// default values at compile time.
+#[doc(hidden)]
+pub const fn __has_value(values: &[OptionValue], value: &CStr) -> bool {
+  let value = value.to_bytes();
+  let mut i = 0;
+  'values: while i < values.len() {
+    let candidate = values[i].value.to_bytes();
+    i += 1;
+    if candidate.len() != value.len() {
+      continue;
+    }
+    let mut j = 0;
+    while j < value.len() {
+      if candidate[j] != value[j] {
+        continue 'values;
+      }
+      j += 1;
+    }
+    return true;
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VALUES: &[OptionValue] = &[
+    OptionValue::new(c"-1"),
+    OptionValue::new(c"0"),
+    OptionValue::new(c"1"),
+  ];
+
+  #[test]
+  fn parse_falls_back_to_default_then_first_parseable_value() {
+    let definition = OptionDefinition::new(c"key", c"Desc", VALUES).with_default(c"1");
+    assert_eq!(definition.parse::<i8>(Some(c"0")), 0);
+    assert_eq!(definition.parse::<i8>(Some(c"x")), 1);
+    assert_eq!(definition.parse::<i8>(None), 1);
+
+    let definition = OptionDefinition::new(c"key", c"Desc", VALUES).with_default(c"-1");
+    assert_eq!(definition.parse::<u8>(None), 0);
+  }
+
+  fn leak_values(count: usize) -> &'static [OptionValue] {
+    let values: Vec<_> = (0..count)
+      .map(|n| {
+        let value = CString::new(n.to_string()).unwrap();
+        OptionValue::new(Box::leak(value.into_boxed_c_str()))
+      })
+      .collect();
+    values.leak()
+  }
+
+  fn c_str<'a>(ptr: *const c_char) -> &'a CStr {
+    unsafe { CStr::from_ptr(ptr) }
+  }
+
+  #[test]
+  fn legacy_value_puts_default_first() {
+    let definition = OptionDefinition::new(c"key", c"Desc", VALUES);
+    assert_eq!(
+      legacy_value(&definition, c"Desc").as_c_str(),
+      c"Desc; -1|0|1"
+    );
+
+    let definition = definition.with_default(c"1");
+    assert_eq!(
+      legacy_value(&definition, c"Desc").as_c_str(),
+      c"Desc; 1|-1|0"
+    );
+  }
+
+  #[test]
+  fn legacy_value_caps_values() {
+    let definition = OptionDefinition::new(c"key", c"Desc", leak_values(200)).with_default(c"5");
+    let value = legacy_value(&definition, c"Desc");
+    let values: Vec<_> = value.to_str().unwrap()["Desc; ".len()..]
+      .split('|')
+      .collect();
+    assert_eq!(values.len(), RETRO_NUM_CORE_OPTION_VALUES_MAX as usize - 1);
+    assert_eq!(values[..3], ["5", "0", "1"]);
+    assert_eq!(values.last(), Some(&"126"));
+  }
+
+  #[test]
+  fn variables_use_translated_descriptions() {
+    const DEFINITIONS: &[OptionDefinition] = &[
+      OptionDefinition::new(c"a", c"Apple", VALUES),
+      OptionDefinition::new(c"b", c"Banana", VALUES).with_default(c"0"),
+    ];
+    const TRANSLATION: OptionsTranslation = OptionsTranslation::new(
+      Language::French,
+      &[OptionDefinition::new(c"b", c"Banane", &[])],
+    );
+    let variables = Variables::new(DEFINITIONS, Some(&TRANSLATION));
+    let raw: Vec<_> = variables
+      .variables
+      .iter()
+      .map(|v| (!v.key.is_null()).then(|| (c_str(v.key), c_str(v.value))))
+      .collect();
+    assert_eq!(
+      raw,
+      [
+        Some((c"a", c"Apple; -1|0|1")),
+        Some((c"b", c"Banane; 0|-1|1")),
+        None
+      ]
+    );
+    assert!(variables.variables[2].value.is_null());
+  }
+
+  #[test]
+  fn core_options_v1_flattens_categories() {
+    const DEFINITIONS: &[OptionDefinition] = &[OptionDefinition::new(c"a", c"Apple", VALUES)
+      .with_desc_categorized(c"Apple (categorized)")
+      .with_info(c"Info")
+      .with_info_categorized(c"Info (categorized)")
+      .with_category(c"fruit")
+      .with_default(c"0")];
+    let options = CoreOptionsV1::new(DEFINITIONS);
+    let [definition, terminator] = &options.definitions[..] else {
+      panic!("expected a definition and a terminator")
+    };
+    assert_eq!(c_str(definition.key), c"a");
+    assert_eq!(c_str(definition.desc), c"Apple");
+    assert_eq!(c_str(definition.info), c"Info");
+    assert_eq!(c_str(definition.default_value), c"0");
+    assert_eq!(c_str(definition.values[0].value), c"-1");
+    assert_eq!(c_str(definition.values[2].value), c"1");
+    assert!(definition.values[3].value.is_null());
+    assert!(terminator.key.is_null());
+    assert!(ptr::eq(options.as_raw(), definition));
+  }
+
+  #[test]
+  fn definitions_cap_values() {
+    let definition = OptionDefinition::new(c"key", c"Desc", leak_values(200));
+    let raw = retro_core_option_definition::from(&definition);
+    let last = raw.values.len() - 1;
+    assert_eq!(c_str(raw.values[last - 1].value), c"126");
+    assert!(raw.values[last].value.is_null());
+  }
+
+  #[test]
+  fn has_value() {
+    assert!(__has_value(VALUES, c"0"));
+    assert!(!__has_value(VALUES, c"2"));
+    assert!(!__has_value(VALUES, c"-"));
+    assert!(!__has_value(&[], c"0"));
+  }
+}
@@ -0,0 +1,177 @@
+use crate::ffi::*;
+use bitbybit::bitfield;
+use core::ptr::NonNull;
+
+/// Timers, CPU feature detection and performance counters, provided by
+/// [`env::Environment::get_perf_interface`].
+///
+/// [`env::Environment::get_perf_interface`]: crate::retro::env::Environment::get_perf_interface
+#[derive(Clone, Copy, Debug)]
+pub struct Perf {
+  get_time_usec: non_null_retro_perf_get_time_usec_t,
+  get_cpu_features: non_null_retro_get_cpu_features_t,
+  get_perf_counter: non_null_retro_perf_get_counter_t,
+  perf_register: non_null_retro_perf_register_t,
+  perf_start: non_null_retro_perf_start_t,
+  perf_stop: non_null_retro_perf_stop_t,
+  perf_log: non_null_retro_perf_log_t,
+}
+
+impl Perf {
+  pub fn new(
+    get_time_usec: non_null_retro_perf_get_time_usec_t,
+    get_cpu_features: non_null_retro_get_cpu_features_t,
+    get_perf_counter: non_null_retro_perf_get_counter_t,
+    perf_register: non_null_retro_perf_register_t,
+    perf_start: non_null_retro_perf_start_t,
+    perf_stop: non_null_retro_perf_stop_t,
+    perf_log: non_null_retro_perf_log_t,
+  ) -> Self {
+    Self {
+      get_time_usec,
+      get_cpu_features,
+      get_perf_counter,
+      perf_register,
+      perf_start,
+      perf_stop,
+      perf_log,
+    }
+  }
+
+  /// Returns the current time in microseconds, from the most accurate timer available.
+  pub fn get_time_usec(&self) -> i64 {
+    unsafe { (self.get_time_usec)() }
+  }
+
+  /// Returns the SIMD and other CPU features the frontend detected.
+  pub fn get_cpu_features(&self) -> CpuFeatures {
+    CpuFeatures::new_with_raw_value(unsafe { (self.get_cpu_features)() })
+  }
+
+  /// Returns a counter that ticks in nanoseconds or CPU cycles, depending on the frontend.
+  pub fn get_perf_counter(&self) -> u64 {
+    unsafe { (self.get_perf_counter)() }
+  }
+
+  /// Creates a performance counter named `ident`.
+  ///
+  /// The frontend keeps pointers to the counters it has seen, so counters are never freed.
+  /// Create each one once, e.g. during [`Core::init`], rather than every frame.
+  ///
+  /// [`Core::init`]: crate::retro::Core::init
+  pub fn counter(&self, ident: &'static CStr) -> PerfCounter {
+    let counter = Box::new(retro_perf_counter {
+      ident: ident.as_ptr(),
+      ..Default::default()
+    });
+    PerfCounter(NonNull::from(Box::leak(counter)))
+  }
+
+  /// Starts `counter`, registering it with the frontend first if necessary.
+  /// The counter stops when the returned guard is dropped.
+  pub fn scope(&self, counter: PerfCounter) -> PerfScope {
+    let ptr = counter.0.as_ptr();
+    unsafe {
+      if !(*ptr).registered {
+        (self.perf_register)(ptr);
+      }
+      (self.perf_start)(ptr);
+    }
+    PerfScope {
+      counter,
+      perf_stop: self.perf_stop,
+    }
+  }
+
+  /// Asks the frontend to log or display its performance counters.
+  pub fn perf_log(&self) {
+    unsafe { (self.perf_log)() }
+  }
+}
+
+/// A performance counter created by [`Perf::counter`].
+///
+/// Counters can't be sent to other threads, since the frontend updates them without
+/// synchronization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PerfCounter(NonNull<retro_perf_counter>);
+
+impl PerfCounter {
+  pub fn ident(&self) -> &'static CStr {
+    unsafe { CStr::from_ptr(self.0.as_ref().ident) }
+  }
+
+  /// The total number of ticks counted so far.
+  pub fn total(&self) -> u64 {
+    unsafe { self.0.as_ref().total }
+  }
+
+  /// The number of times the counter was started and stopped.
+  pub fn call_count(&self) -> u64 {
+    unsafe { self.0.as_ref().call_cnt }
+  }
+}
+
+/// A running [`PerfCounter`], which stops when dropped.
+#[must_use = "the counter stops when the scope is dropped"]
+#[derive(Debug)]
+pub struct PerfScope {
+  counter: PerfCounter,
+  perf_stop: non_null_retro_perf_stop_t,
+}
+
+impl Drop for PerfScope {
+  fn drop(&mut self) {
+    unsafe { (self.perf_stop)(self.counter.0.as_ptr()) }
+  }
+}
+
+/// The CPU features reported by [`Perf::get_cpu_features`], mirroring `RETRO_SIMD_*`.
+#[bitfield(u64, default: 0)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct CpuFeatures {
+  #[bit(0, rw)]
+  sse: bool,
+  #[bit(1, rw)]
+  sse2: bool,
+  #[bit(2, rw)]
+  vmx: bool,
+  #[bit(3, rw)]
+  vmx128: bool,
+  #[bit(4, rw)]
+  avx: bool,
+  #[bit(5, rw)]
+  neon: bool,
+  #[bit(6, rw)]
+  sse3: bool,
+  #[bit(7, rw)]
+  ssse3: bool,
+  #[bit(8, rw)]
+  mmx: bool,
+  #[bit(9, rw)]
+  mmxext: bool,
+  #[bit(10, rw)]
+  sse4: bool,
+  #[bit(11, rw)]
+  sse42: bool,
+  #[bit(12, rw)]
+  avx2: bool,
+  #[bit(13, rw)]
+  vfpu: bool,
+  #[bit(14, rw)]
+  ps: bool,
+  #[bit(15, rw)]
+  aes: bool,
+  #[bit(16, rw)]
+  vfpv3: bool,
+  #[bit(17, rw)]
+  vfpv4: bool,
+  #[bit(18, rw)]
+  popcnt: bool,
+  #[bit(19, rw)]
+  movbe: bool,
+  #[bit(20, rw)]
+  cmov: bool,
+  #[bit(21, rw)]
+  asimd: bool,
+}
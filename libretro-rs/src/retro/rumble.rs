@@ -0,0 +1,82 @@
+use crate::ffi::*;
+use crate::retro::env::Environment;
+use crate::retro::*;
+use std::sync::{Mutex, PoisonError};
+
+/// The rumble motors of a controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RumbleEffect {
+  Strong,
+  Weak,
+}
+
+impl From<RumbleEffect> for retro_rumble_effect {
+  fn from(effect: RumbleEffect) -> Self {
+    match effect {
+      RumbleEffect::Strong => retro_rumble_effect::RETRO_RUMBLE_STRONG,
+      RumbleEffect::Weak => retro_rumble_effect::RETRO_RUMBLE_WEAK,
+    }
+  }
+}
+
+/// Force feedback, provided by [`Environment::get_rumble_interface`].
+///
+/// Motors left running are stopped automatically when the game is reset or unloaded,
+/// so cores don't have to stop them in [`Core::reset`] or [`Core::unload_game`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug)]
+pub struct Rumble(non_null_retro_set_rumble_state_t);
+
+impl Rumble {
+  pub fn new(set_rumble_state: non_null_retro_set_rumble_state_t) -> Self {
+    Self(set_rumble_state)
+  }
+
+  /// Sets the strength of a motor of the controller on `port`, where `0` stops it.
+  /// Returns true if the frontend accepted the request.
+  pub fn set(&self, port: DevicePort, effect: RumbleEffect, strength: u16) -> bool {
+    let accepted = unsafe { self.0(port.into_inner(), effect.into(), strength) };
+    if accepted {
+      let mut active = ACTIVE.lock().unwrap_or_else(PoisonError::into_inner);
+      active.retain(|&motor| motor != (port, effect));
+      if strength > 0 {
+        active.push((port, effect));
+      }
+    }
+    accepted
+  }
+
+  /// Sets the strength of the strong motor of the controller on `port`.
+  pub fn set_strong(&self, port: DevicePort, strength: u16) -> bool {
+    self.set(port, RumbleEffect::Strong, strength)
+  }
+
+  /// Sets the strength of the weak motor of the controller on `port`.
+  pub fn set_weak(&self, port: DevicePort, strength: u16) -> bool {
+    self.set(port, RumbleEffect::Weak, strength)
+  }
+
+  /// Stops both motors of the controller on `port`.
+  pub fn stop(&self, port: DevicePort) -> bool {
+    let strong = self.set_strong(port, 0);
+    let weak = self.set_weak(port, 0);
+    strong && weak
+  }
+}
+
+/// The motors a [`Rumble`] has left running. The frontend's rumble interface is shared by
+/// the whole process, so this is too.
+static ACTIVE: Mutex<Vec<(DevicePort, RumbleEffect)>> = Mutex::new(Vec::new());
+
+/// Stops the motors a [`Rumble`] has left running.
+pub(crate) fn stop_active(env: &impl Environment) {
+  let active = std::mem::take(&mut *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner));
+  if active.is_empty() {
+    return;
+  }
+  if let Ok(rumble) = env.get_rumble_interface() {
+    for (port, effect) in active {
+      rumble.set(port, effect, 0);
+    }
+  }
+}
@@ -0,0 +1,136 @@
+use crate::ffi::*;
+use crate::retro::*;
+
+/// The sensors a frontend can provide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SensorType {
+  Accelerometer,
+  Gyroscope,
+  Illuminance,
+}
+
+impl SensorType {
+  fn action(self, enabled: bool) -> retro_sensor_action {
+    use retro_sensor_action::*;
+    match (self, enabled) {
+      (Self::Accelerometer, true) => RETRO_SENSOR_ACCELEROMETER_ENABLE,
+      (Self::Accelerometer, false) => RETRO_SENSOR_ACCELEROMETER_DISABLE,
+      (Self::Gyroscope, true) => RETRO_SENSOR_GYROSCOPE_ENABLE,
+      (Self::Gyroscope, false) => RETRO_SENSOR_GYROSCOPE_DISABLE,
+      (Self::Illuminance, true) => RETRO_SENSOR_ILLUMINANCE_ENABLE,
+      (Self::Illuminance, false) => RETRO_SENSOR_ILLUMINANCE_DISABLE,
+    }
+  }
+}
+
+/// The values a frontend can report from a sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SensorInput {
+  AccelerometerX = 0,
+  AccelerometerY = 1,
+  AccelerometerZ = 2,
+  GyroscopeX = 3,
+  GyroscopeY = 4,
+  GyroscopeZ = 5,
+  Illuminance = 6,
+}
+
+impl SensorInput {
+  /// The sensor that reports this value.
+  pub fn sensor(self) -> SensorType {
+    match self {
+      Self::AccelerometerX | Self::AccelerometerY | Self::AccelerometerZ => {
+        SensorType::Accelerometer
+      }
+      Self::GyroscopeX | Self::GyroscopeY | Self::GyroscopeZ => SensorType::Gyroscope,
+      Self::Illuminance => SensorType::Illuminance,
+    }
+  }
+}
+
+impl From<SensorInput> for c_uint {
+  fn from(input: SensorInput) -> Self {
+    input as c_uint
+  }
+}
+
+/// Motion and light sensors, provided by [`env::Environment::get_sensor_interface`].
+///
+/// A sensor must be enabled on a port before it can be read. Reads return [`None`] if the
+/// sensor isn't enabled, which includes sensors the frontend failed to enable.
+#[derive(Clone, Debug)]
+pub struct Sensors {
+  set_sensor_state: non_null_retro_set_sensor_state_t,
+  get_sensor_input: retro_sensor_get_input_t,
+  enabled: Vec<(DevicePort, SensorType)>,
+}
+
+impl Sensors {
+  pub fn new(
+    set_sensor_state: non_null_retro_set_sensor_state_t,
+    get_sensor_input: retro_sensor_get_input_t,
+  ) -> Self {
+    Self {
+      set_sensor_state,
+      get_sensor_input,
+      enabled: Vec::new(),
+    }
+  }
+
+  /// Enables `sensor` on `port`, asking for `rate` updates per second.
+  /// Returns true if the frontend enabled the sensor.
+  pub fn enable(&mut self, port: DevicePort, sensor: SensorType, rate: c_uint) -> bool {
+    let enabled = self.set_state(port, sensor, true, rate);
+    if enabled && !self.is_enabled(port, sensor) {
+      self.enabled.push((port, sensor));
+    }
+    enabled
+  }
+
+  /// Disables `sensor` on `port`. Returns true if the frontend disabled the sensor.
+  pub fn disable(&mut self, port: DevicePort, sensor: SensorType) -> bool {
+    self.enabled.retain(|&enabled| enabled != (port, sensor));
+    self.set_state(port, sensor, false, 0)
+  }
+
+  /// Returns true if `sensor` is enabled on `port`.
+  pub fn is_enabled(&self, port: DevicePort, sensor: SensorType) -> bool {
+    self.enabled.contains(&(port, sensor))
+  }
+
+  /// Reads a value from a sensor on `port`.
+  pub fn read(&self, port: DevicePort, input: SensorInput) -> Option<f32> {
+    let get_sensor_input = self.get_sensor_input?;
+    if !self.is_enabled(port, input.sensor()) {
+      return None;
+    }
+    Some(unsafe { get_sensor_input(port.into_inner(), input.into()) })
+  }
+
+  /// Reads the acceleration along the X, Y and Z axes.
+  pub fn accelerometer(&self, port: DevicePort) -> Option<[f32; 3]> {
+    Some([
+      self.read(port, SensorInput::AccelerometerX)?,
+      self.read(port, SensorInput::AccelerometerY)?,
+      self.read(port, SensorInput::AccelerometerZ)?,
+    ])
+  }
+
+  /// Reads the rotation around the X, Y and Z axes.
+  pub fn gyroscope(&self, port: DevicePort) -> Option<[f32; 3]> {
+    Some([
+      self.read(port, SensorInput::GyroscopeX)?,
+      self.read(port, SensorInput::GyroscopeY)?,
+      self.read(port, SensorInput::GyroscopeZ)?,
+    ])
+  }
+
+  /// Reads the ambient light level.
+  pub fn illuminance(&self, port: DevicePort) -> Option<f32> {
+    self.read(port, SensorInput::Illuminance)
+  }
+
+  fn set_state(&self, port: DevicePort, sensor: SensorType, enabled: bool, rate: c_uint) -> bool {
+    unsafe { (self.set_sensor_state)(port.into_inner(), sensor.action(enabled), rate) }
+  }
+}
@@ -0,0 +1,559 @@
+use crate::ffi::*;
+use core::fmt;
+use core::ptr::{self, NonNull};
+use std::ffi::CString;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Reads a function of the frontend's interface. Functions added after version 1 must only be
+/// read once [`require`] has confirmed the frontend's struct is long enough to have them.
+macro_rules! field {
+  ($iface:expr, $field:ident) => {
+    ptr::addr_of!((*$iface.as_ptr()).$field).read()
+  };
+}
+
+/// The frontend's virtual file system, provided by [`env::Environment::get_vfs_interface`].
+/// Going through the VFS lets cores open files the frontend can see but the platform can't,
+/// such as files inside archives or behind Android's storage access framework.
+///
+/// If the frontend doesn't provide a VFS, every operation falls back to [`std::fs`].
+///
+/// Paths are UTF-8 and use `/` as the directory separator.
+///
+/// [`env::Environment::get_vfs_interface`]: crate::retro::env::Environment::get_vfs_interface
+#[derive(Clone, Copy, Default)]
+pub struct Vfs {
+  // Frontends only allocate the fields of `version`, so this is never turned into a reference.
+  iface: Option<NonNull<retro_vfs_interface>>,
+  version: u32,
+}
+
+impl Vfs {
+  /// The newest version of the interface this crate knows how to use.
+  pub const MAX_VERSION: u32 = 3;
+
+  /// # Safety
+  /// `iface` must point to an interface of at least `version` that outlives the core.
+  pub unsafe fn new(iface: NonNull<retro_vfs_interface>, version: u32) -> Self {
+    Self {
+      iface: Some(iface),
+      version,
+    }
+  }
+
+  /// A [`Vfs`] that always uses [`std::fs`].
+  pub fn std() -> Self {
+    Self::default()
+  }
+
+  /// Returns the version of the frontend's interface, or [`None`] when using [`std::fs`].
+  /// Version 1 can't truncate files, and versions below 3 can't stat paths, make
+  /// directories or list them.
+  pub fn version(&self) -> Option<u32> {
+    self.iface.map(|_| self.version)
+  }
+
+  /// Opens an existing file for reading.
+  pub fn open(&self, path: impl AsRef<Path>) -> io::Result<VfsFile> {
+    self.open_with(path, VfsOpenOptions::new().with_read())
+  }
+
+  /// Creates a file for writing, discarding the contents of any existing file.
+  pub fn create(&self, path: impl AsRef<Path>) -> io::Result<VfsFile> {
+    self.open_with(path, VfsOpenOptions::new().with_write())
+  }
+
+  pub fn open_with(&self, path: impl AsRef<Path>, options: VfsOpenOptions) -> io::Result<VfsFile> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => {
+        return options
+          .open_std(path.as_ref())
+          .map(|file| VfsFile(FileInner::Std(file)))
+      }
+    };
+    let open = func(unsafe { field!(iface, open) })?;
+    let path = c_path(path.as_ref())?;
+    let handle = unsafe { open(path.as_ptr(), options.mode(), options.hints()) };
+    match NonNull::new(handle) {
+      Some(handle) => Ok(VfsFile(FileInner::Frontend {
+        iface,
+        version: self.version,
+        handle,
+      })),
+      None => Err(failed("open")),
+    }
+  }
+
+  /// Returns information about a file or directory.
+  pub fn stat(&self, path: impl AsRef<Path>) -> io::Result<VfsMetadata> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => return fs::metadata(path).map(VfsMetadata::from),
+    };
+    require(self.version, 3)?;
+    let stat = func(unsafe { field!(iface, stat) })?;
+    let path = c_path(path.as_ref())?;
+    let mut size = 0;
+    let flags = unsafe { stat(path.as_ptr(), &mut size) } as u32;
+    if flags & RETRO_VFS_STAT_IS_VALID == 0 {
+      return Err(io::Error::new(io::ErrorKind::NotFound, "VFS: no such file"));
+    }
+    Ok(VfsMetadata {
+      size: size.max(0) as u64,
+      is_dir: flags & RETRO_VFS_STAT_IS_DIRECTORY != 0,
+      is_character_special: flags & RETRO_VFS_STAT_IS_CHARACTER_SPECIAL != 0,
+    })
+  }
+
+  /// Creates a directory. Its parent must already exist.
+  pub fn mkdir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => return fs::create_dir(path),
+    };
+    require(self.version, 3)?;
+    let mkdir = func(unsafe { field!(iface, mkdir) })?;
+    let path = c_path(path.as_ref())?;
+    match unsafe { mkdir(path.as_ptr()) } {
+      0 => Ok(()),
+      -2 => Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "VFS: directory already exists",
+      )),
+      _ => Err(failed("mkdir")),
+    }
+  }
+
+  /// Deletes a file.
+  pub fn remove(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => return fs::remove_file(path),
+    };
+    let remove = func(unsafe { field!(iface, remove) })?;
+    let path = c_path(path.as_ref())?;
+    check(unsafe { remove(path.as_ptr()) }, "remove")
+  }
+
+  /// Renames a file, replacing `to` if it already exists.
+  pub fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => return fs::rename(from, to),
+    };
+    let rename = func(unsafe { field!(iface, rename) })?;
+    let from = c_path(from.as_ref())?;
+    let to = c_path(to.as_ref())?;
+    check(unsafe { rename(from.as_ptr(), to.as_ptr()) }, "rename")
+  }
+
+  /// Lists the entries of a directory, not including `.` and `..`. Hidden entries are
+  /// only included if `include_hidden` is true.
+  pub fn read_dir(&self, path: impl AsRef<Path>, include_hidden: bool) -> io::Result<VfsReadDir> {
+    let iface = match self.iface {
+      Some(iface) => iface,
+      None => {
+        return fs::read_dir(path).map(|entries| {
+          VfsReadDir(DirInner::Std {
+            entries,
+            include_hidden,
+          })
+        })
+      }
+    };
+    require(self.version, 3)?;
+    let opendir = func(unsafe { field!(iface, opendir) })?;
+    let path = c_path(path.as_ref())?;
+    let handle = unsafe { opendir(path.as_ptr(), include_hidden) };
+    match NonNull::new(handle) {
+      Some(handle) => Ok(VfsReadDir(DirInner::Frontend {
+        iface,
+        version: self.version,
+        handle,
+      })),
+      None => Err(failed("opendir")),
+    }
+  }
+}
+
+impl fmt::Debug for Vfs {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Vfs")
+      .field("version", &self.version())
+      .finish_non_exhaustive()
+  }
+}
+
+/// How [`Vfs::open_with`] opens a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VfsOpenOptions {
+  read: bool,
+  write: bool,
+  update_existing: bool,
+  frequent_access: bool,
+}
+
+impl VfsOpenOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_read(mut self) -> Self {
+    self.read = true;
+    self
+  }
+
+  /// Opens the file for writing, creating it if necessary. Unless combined with
+  /// [`VfsOpenOptions::with_update_existing`], the contents of an existing file are discarded.
+  pub fn with_write(mut self) -> Self {
+    self.write = true;
+    self
+  }
+
+  /// Keeps the contents of an existing file opened for writing.
+  pub fn with_update_existing(mut self) -> Self {
+    self.update_existing = true;
+    self
+  }
+
+  /// Hints that the file will be accessed often, so the frontend should cache it aggressively.
+  pub fn with_frequent_access(mut self) -> Self {
+    self.frequent_access = true;
+    self
+  }
+
+  fn mode(&self) -> c_uint {
+    let mut mode = 0;
+    if self.read {
+      mode |= RETRO_VFS_FILE_ACCESS_READ;
+    }
+    if self.write {
+      mode |= RETRO_VFS_FILE_ACCESS_WRITE;
+    }
+    if self.update_existing {
+      mode |= RETRO_VFS_FILE_ACCESS_UPDATE_EXISTING;
+    }
+    mode
+  }
+
+  fn hints(&self) -> c_uint {
+    if self.frequent_access {
+      RETRO_VFS_FILE_ACCESS_HINT_FREQUENT_ACCESS
+    } else {
+      RETRO_VFS_FILE_ACCESS_HINT_NONE
+    }
+  }
+
+  fn open_std(&self, path: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+      .read(self.read)
+      .write(self.write)
+      .create(self.write)
+      .truncate(self.write && !self.update_existing)
+      .open(path)
+  }
+}
+
+/// An open file, returned by [`Vfs::open`]. The file is closed when dropped.
+#[derive(Debug)]
+pub struct VfsFile(FileInner);
+
+enum FileInner {
+  Frontend {
+    iface: NonNull<retro_vfs_interface>,
+    version: u32,
+    handle: NonNull<retro_vfs_file_handle>,
+  },
+  Std(fs::File),
+}
+
+impl fmt::Debug for FileInner {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Frontend { version, .. } => f
+        .debug_struct("Frontend")
+        .field("version", version)
+        .finish_non_exhaustive(),
+      Self::Std(file) => f.debug_tuple("Std").field(file).finish(),
+    }
+  }
+}
+
+impl VfsFile {
+  /// Returns the size of the file in bytes.
+  pub fn size(&self) -> io::Result<u64> {
+    match &self.0 {
+      FileInner::Frontend { iface, handle, .. } => {
+        let size = unsafe { func(field!(*iface, size))?(handle.as_ptr()) };
+        u64::try_from(size).map_err(|_| failed("size"))
+      }
+      FileInner::Std(file) => file.metadata().map(|metadata| metadata.len()),
+    }
+  }
+
+  /// Truncates or extends the file to `len` bytes.
+  pub fn truncate(&mut self, len: u64) -> io::Result<()> {
+    match &mut self.0 {
+      FileInner::Frontend {
+        iface,
+        version,
+        handle,
+      } => {
+        require(*version, 2)?;
+        let len = i64::try_from(len).map_err(|_| invalid_input("VFS: length out of range"))?;
+        let result = unsafe { func(field!(*iface, truncate))?(handle.as_ptr(), len) };
+        check(result, "truncate")
+      }
+      FileInner::Std(file) => file.set_len(len),
+    }
+  }
+}
+
+impl Read for VfsFile {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match &mut self.0 {
+      FileInner::Frontend { iface, handle, .. } => {
+        let read = func(unsafe { field!(*iface, read) })?;
+        let len = unsafe { read(handle.as_ptr(), buf.as_mut_ptr().cast(), buf.len() as u64) };
+        usize::try_from(len).map_err(|_| failed("read"))
+      }
+      FileInner::Std(file) => file.read(buf),
+    }
+  }
+}
+
+impl Write for VfsFile {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match &mut self.0 {
+      FileInner::Frontend { iface, handle, .. } => {
+        let write = func(unsafe { field!(*iface, write) })?;
+        let len = unsafe { write(handle.as_ptr(), buf.as_ptr().cast(), buf.len() as u64) };
+        usize::try_from(len).map_err(|_| failed("write"))
+      }
+      FileInner::Std(file) => file.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match &mut self.0 {
+      FileInner::Frontend { iface, handle, .. } => {
+        let result = unsafe { func(field!(*iface, flush))?(handle.as_ptr()) };
+        check(result, "flush")
+      }
+      FileInner::Std(file) => file.flush(),
+    }
+  }
+}
+
+impl Seek for VfsFile {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    match &mut self.0 {
+      FileInner::Frontend { iface, handle, .. } => {
+        let (offset, whence) = match pos {
+          SeekFrom::Start(offset) => (
+            i64::try_from(offset).map_err(|_| invalid_input("VFS: offset out of range"))?,
+            RETRO_VFS_SEEK_POSITION_START,
+          ),
+          SeekFrom::Current(offset) => (offset, RETRO_VFS_SEEK_POSITION_CURRENT),
+          SeekFrom::End(offset) => (offset, RETRO_VFS_SEEK_POSITION_END),
+        };
+        let seek = func(unsafe { field!(*iface, seek) })?;
+        let position = unsafe { seek(handle.as_ptr(), offset, whence as c_int) };
+        u64::try_from(position).map_err(|_| failed("seek"))
+      }
+      FileInner::Std(file) => file.seek(pos),
+    }
+  }
+}
+
+impl Drop for FileInner {
+  fn drop(&mut self) {
+    if let Self::Frontend { iface, handle, .. } = self {
+      if let Some(close) = unsafe { field!(*iface, close) } {
+        unsafe { close(handle.as_ptr()) };
+      }
+    }
+  }
+}
+
+/// Information about a file or directory, returned by [`Vfs::stat`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VfsMetadata {
+  /// The size in bytes. Frontends report sizes as 32-bit values.
+  pub size: u64,
+  pub is_dir: bool,
+  pub is_character_special: bool,
+}
+
+impl From<fs::Metadata> for VfsMetadata {
+  fn from(metadata: fs::Metadata) -> Self {
+    #[cfg(unix)]
+    let is_character_special = {
+      use std::os::unix::fs::FileTypeExt;
+      metadata.file_type().is_char_device()
+    };
+    #[cfg(not(unix))]
+    let is_character_special = false;
+    Self {
+      size: metadata.len(),
+      is_dir: metadata.is_dir(),
+      is_character_special,
+    }
+  }
+}
+
+/// An iterator over the entries of a directory, returned by [`Vfs::read_dir`].
+/// The directory is closed when dropped.
+#[derive(Debug)]
+pub struct VfsReadDir(DirInner);
+
+enum DirInner {
+  Frontend {
+    iface: NonNull<retro_vfs_interface>,
+    version: u32,
+    handle: NonNull<retro_vfs_dir_handle>,
+  },
+  Std {
+    entries: fs::ReadDir,
+    include_hidden: bool,
+  },
+}
+
+impl fmt::Debug for DirInner {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Frontend { version, .. } => f
+        .debug_struct("Frontend")
+        .field("version", version)
+        .finish_non_exhaustive(),
+      Self::Std {
+        entries,
+        include_hidden,
+      } => f
+        .debug_struct("Std")
+        .field("entries", entries)
+        .field("include_hidden", include_hidden)
+        .finish(),
+    }
+  }
+}
+
+impl Iterator for VfsReadDir {
+  type Item = io::Result<VfsDirEntry>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match &mut self.0 {
+      // Directories can only be opened with version 3 or later, which has these functions.
+      DirInner::Frontend { iface, handle, .. } => loop {
+        let functions = unsafe {
+          (
+            field!(*iface, readdir),
+            field!(*iface, dirent_get_name),
+            field!(*iface, dirent_is_dir),
+          )
+        };
+        let (readdir, get_name, is_dir) = match functions {
+          (Some(readdir), Some(get_name), Some(is_dir)) => (readdir, get_name, is_dir),
+          _ => return Some(Err(unsupported())),
+        };
+        if !unsafe { readdir(handle.as_ptr()) } {
+          return None;
+        }
+        let name = unsafe { get_name(handle.as_ptr()) };
+        if name.is_null() {
+          return Some(Err(failed("dirent_get_name")));
+        }
+        let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+        if name == "." || name == ".." {
+          continue;
+        }
+        return Some(Ok(VfsDirEntry {
+          name: name.into_owned(),
+          is_dir: unsafe { is_dir(handle.as_ptr()) },
+        }));
+      },
+      DirInner::Std {
+        entries,
+        include_hidden,
+      } => loop {
+        let entry = match entries.next()? {
+          Ok(entry) => entry,
+          Err(error) => return Some(Err(error)),
+        };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !*include_hidden && name.starts_with('.') {
+          continue;
+        }
+        return Some(entry.file_type().map(|file_type| VfsDirEntry {
+          name,
+          is_dir: file_type.is_dir(),
+        }));
+      },
+    }
+  }
+}
+
+impl Drop for DirInner {
+  fn drop(&mut self) {
+    if let Self::Frontend { iface, handle, .. } = self {
+      if let Some(closedir) = unsafe { field!(*iface, closedir) } {
+        unsafe { closedir(handle.as_ptr()) };
+      }
+    }
+  }
+}
+
+/// An entry returned by [`VfsReadDir`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VfsDirEntry {
+  /// The name of the entry, without the path of its directory.
+  pub name: String,
+  pub is_dir: bool,
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+  let path = path
+    .to_str()
+    .ok_or_else(|| invalid_input("VFS: path is not valid UTF-8"))?;
+  CString::new(path).map_err(|_| invalid_input("VFS: path contains a nul byte"))
+}
+
+/// Returns the function, or an error if the frontend doesn't provide it.
+fn func<F>(f: Option<F>) -> io::Result<F> {
+  f.ok_or_else(unsupported)
+}
+
+/// Returns an error if the frontend's interface predates `since`. The functions added in later
+/// versions are past the end of the frontend's struct, so they must not be read.
+fn require(version: u32, since: u32) -> io::Result<()> {
+  if version >= since {
+    Ok(())
+  } else {
+    Err(unsupported())
+  }
+}
+
+fn check(result: impl Into<i64>, op: &str) -> io::Result<()> {
+  match result.into() {
+    0 => Ok(()),
+    _ => Err(failed(op)),
+  }
+}
+
+fn unsupported() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::Unsupported,
+    "VFS: not supported by the frontend",
+  )
+}
+
+fn failed(op: &str) -> io::Error {
+  io::Error::other(format!("VFS: {op} failed"))
+}
+
+fn invalid_input(message: &'static str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, message)
+}
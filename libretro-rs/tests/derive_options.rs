@@ -0,0 +1,111 @@
+#![cfg(feature = "derive")]
+
+use libretro_rs::retro::options::*;
+use std::ffi::CStr;
+
+#[derive(Clone, Debug, PartialEq, CoreOptionValue)]
+enum Palette {
+  #[option(label = "Grayscale")]
+  Gray,
+  #[option(value = "lcd", label = "LCD Green")]
+  Green,
+}
+
+#[derive(Debug, PartialEq, CoreOptions)]
+#[options(prefix = "foo_", category(key = "video", desc = "Video"))]
+struct Options {
+  #[option(desc = "Palette", category = "video", default = "lcd")]
+  palette: Palette,
+  #[option(desc = "Offset", min = -2, max = 2, default = 0)]
+  offset: i8,
+  #[option(key = "foo_audio", desc = "Audio", default = true)]
+  audio_enabled: bool,
+  #[option(
+    desc = "Volume",
+    min = 0,
+    max = 100,
+    step = 50,
+    visible_if(audio_enabled = true)
+  )]
+  volume: u8,
+}
+
+fn values(definition: &OptionDefinition) -> Vec<&'static CStr> {
+  definition.values().iter().map(OptionValue::value).collect()
+}
+
+#[test]
+fn generates_tables() {
+  let [category] = Options::CATEGORIES else {
+    panic!("expected a single category")
+  };
+  assert_eq!((category.key(), category.desc()), (c"video", c"Video"));
+
+  let [palette, offset, audio, volume] = Options::DEFINITIONS else {
+    panic!("expected four definitions")
+  };
+  assert_eq!(palette.key(), c"foo_palette");
+  assert_eq!(palette.category_key(), Some(c"video"));
+  assert_eq!(palette.default_value(), Some(c"lcd"));
+  assert_eq!(palette.values(), Palette::VALUES);
+  assert_eq!(Palette::VALUES[0].label(), Some(c"Grayscale"));
+  assert_eq!(values(palette), [c"gray", c"lcd"]);
+
+  assert_eq!(offset.key(), c"foo_offset");
+  assert_eq!(values(offset), [c"-2", c"-1", c"0", c"1", c"2"]);
+  assert_eq!(offset.default_value(), Some(c"0"));
+
+  assert_eq!(audio.key(), c"foo_audio");
+  assert_eq!(values(audio), [c"disabled", c"enabled"]);
+  assert_eq!(audio.default_value(), Some(c"enabled"));
+
+  assert_eq!(values(volume), [c"0", c"50", c"100"]);
+  assert_eq!(volume.default_value(), Some(c"0"));
+  let [condition] = volume.visible_if() else {
+    panic!("expected a single condition")
+  };
+  assert_eq!(condition.key(), c"foo_audio");
+  assert_eq!(condition.values(), [c"enabled"]);
+}
+
+#[test]
+fn from_values_uses_defaults_for_missing_and_invalid_values() {
+  let options = Options::from_values(|key| match key.to_bytes() {
+    b"foo_palette" => Some(c"gray"),
+    b"foo_offset" => Some(c"-2"),
+    b"foo_audio" => Some(c"maybe"),
+    _ => None,
+  });
+  assert_eq!(
+    options,
+    Options {
+      palette: Palette::Gray,
+      offset: -2,
+      audio_enabled: true,
+      volume: 0,
+    }
+  );
+}
+
+#[test]
+fn update_reports_changes() {
+  let mut options = Options::from_values(|_| None);
+  let mut new = Options::from_values(|_| None);
+  assert_eq!(options.update(Options::from_values(|_| None)), []);
+
+  new.palette = Palette::Gray;
+  new.volume = 50;
+  let changes = options.update(new);
+  assert_eq!(
+    changes,
+    [
+      OptionsChange::Palette(Palette::Gray),
+      OptionsChange::Volume(50)
+    ]
+  );
+  assert_eq!(changes[0].definition().key(), c"foo_palette");
+  assert_eq!(changes[0].value().as_ref(), c"gray");
+  assert_eq!(changes[1].value().as_ref(), c"50");
+  assert_eq!(options.palette, Palette::Gray);
+  assert_eq!(options.volume, 50);
+}